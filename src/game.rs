@@ -1,6 +1,7 @@
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use std::fmt;
 use std::ops::Range;
+use std::str::FromStr;
 
 pub type Player = u32;
 
@@ -33,9 +34,10 @@ impl Card {
         Card { color: color, value: value }
     }
 }
+// pads to the formatter's width, so cards can be aligned in columns
 impl fmt::Display for Card {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}{}", self.color, self.value)
+        f.pad(&format!("{}{}", self.color, self.value))
     }
 }
 impl fmt::Debug for Card {
@@ -43,6 +45,21 @@ impl fmt::Debug for Card {
         write!(f, "{}{}", self.color, self.value)
     }
 }
+// parses the shorthand used by Display, e.g. "r1"
+impl FromStr for Card {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Card, String> {
+        let mut chars = s.chars();
+        let color = match chars.next() {
+            Some(color) if COLORS.contains(&color) => color,
+            _ => { return Err(format!("Invalid card color in {:?}", s)); }
+        };
+        match chars.as_str().parse::<Value>() {
+            Ok(value) if VALUES.contains(&value) => Ok(Card::new(color, value)),
+            _ => Err(format!("Invalid card value in {:?}", s)),
+        }
+    }
+}
 
 #[derive(Debug,Clone,Eq,PartialEq)]
 pub struct CardCounts {
@@ -99,6 +116,28 @@ impl fmt::Display for CardCounts {
 
 pub type Cards = Vec<Card>;
 
+// every card of the game, in order of color and value
+pub fn full_deck() -> Cards {
+    let mut deck = Cards::new();
+    for &color in COLORS.iter() {
+        for &value in VALUES.iter() {
+            for _ in 0..get_count_for_value(value) {
+                deck.push(Card::new(color, value));
+            }
+        }
+    }
+    deck
+}
+
+// number of copies of each value among the cards, across all colors
+pub fn value_distribution(cards: &Cards) -> FnvHashMap<Value, u32> {
+    let mut distribution = FnvHashMap::default();
+    for card in cards {
+        *distribution.entry(card.value).or_insert(0) += 1;
+    }
+    distribution
+}
+
 #[derive(Debug,Clone,Eq,PartialEq)]
 pub struct Discard {
     pub cards: Cards,
@@ -124,6 +163,27 @@ impl Discard {
         self.counts.increment(&card);
         self.cards.push(card);
     }
+
+    // most recently discarded card, including misplays
+    pub fn last_discarded(&self) -> Option<&Card> {
+        self.cards.last()
+    }
+
+    // for each color, the values with copies in the discard,
+    // along with how many were discarded out of how many exist
+    pub fn criticality_report(&self) -> Vec<(Color, Vec<(Value, u32, u32)>)> {
+        COLORS.iter().map(|&color| {
+            let values = VALUES.iter().filter_map(|&value| {
+                let discarded = self.counts.get_count(&Card::new(color, value));
+                if discarded > 0 {
+                    Some((value, discarded, get_count_for_value(value)))
+                } else {
+                    None
+                }
+            }).collect::<Vec<_>>();
+            (color, values)
+        }).collect()
+    }
 }
 impl fmt::Display for Discard {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -198,20 +258,87 @@ impl fmt::Display for Hinted {
     }
 }
 
-#[derive(Debug,Clone,Eq,PartialEq)]
+#[derive(Debug,Clone,Hash,Eq,PartialEq)]
 pub struct Hint {
     pub player: Player,
     pub hinted: Hinted,
 }
 
-// represents the choice a player made in a given turn
+// set of hints, e.g. to keep track of which hints have been given
 #[derive(Debug,Clone,Eq,PartialEq)]
+pub struct HintSet {
+    hints: FnvHashSet<Hint>,
+}
+impl HintSet {
+    pub fn new() -> HintSet {
+        HintSet {
+            hints: FnvHashSet::default(),
+        }
+    }
+
+    // returns whether the hint was not in the set yet
+    pub fn insert(&mut self, hint: Hint) -> bool {
+        self.hints.insert(hint)
+    }
+
+    pub fn contains(&self, hint: &Hint) -> bool {
+        self.hints.contains(hint)
+    }
+
+    pub fn len(&self) -> usize {
+        self.hints.len()
+    }
+}
+
+// represents the choice a player made in a given turn
+#[derive(Debug,Clone,Hash,Eq,PartialEq)]
 pub enum TurnChoice {
     Hint(Hint),
     Discard(usize), // index of card to discard
     Play(usize),    // index of card to play
 }
 
+// shorthand such as "play 0", "discard 3" or "hint 1 r", e.g. to record and replay games
+impl fmt::Display for TurnChoice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &TurnChoice::Hint(ref hint) => { write!(f, "hint {} {}", hint.player, hint.hinted) }
+            &TurnChoice::Discard(index) => { write!(f, "discard {}", index) }
+            &TurnChoice::Play(index) => { write!(f, "play {}", index) }
+        }
+    }
+}
+// parses the shorthand used by Display
+impl FromStr for TurnChoice {
+    type Err = String;
+    fn from_str(s: &str) -> Result<TurnChoice, String> {
+        let words = s.split_whitespace().collect::<Vec<_>>();
+        let parse_index = |word: &str| {
+            word.parse::<usize>().map_err(|_| format!("Invalid index in {:?}", s))
+        };
+        match (words.get(0).cloned(), words.len()) {
+            (Some("play"), 2) => Ok(TurnChoice::Play(parse_index(words[1])?)),
+            (Some("discard"), 2) => Ok(TurnChoice::Discard(parse_index(words[1])?)),
+            (Some("hint"), 3) => {
+                let player = words[1].parse::<Player>().map_err(|_| format!("Invalid player in {:?}", s))?;
+                let mut chars = words[2].chars();
+                let hinted = match (chars.next(), chars.next()) {
+                    (Some(color), None) if COLORS.contains(&color) => Hinted::Color(color),
+                    _ => match words[2].parse::<Value>() {
+                        Ok(value) if VALUES.contains(&value) => Hinted::Value(value),
+                        _ => { return Err(format!("Invalid hint in {:?}", s)); }
+                    },
+                };
+                Ok(TurnChoice::Hint(Hint {
+                    player: player,
+                    hinted: hinted,
+                }))
+            }
+            _ => Err(format!("Invalid choice {:?}", s)),
+        }
+    }
+}
+
 // represents what happened in a turn
 #[derive(Debug,Clone,Eq,PartialEq)]
 pub enum TurnResult {
@@ -229,6 +356,18 @@ pub struct TurnRecord {
 }
 pub type TurnHistory = Vec<TurnRecord>;
 
+// something that happened during a turn, for drivers that react to changes
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub enum GameEvent {
+    Hinted(Hint, Vec<bool>),
+    Discarded(Card),
+    Played(Card, bool), // card played successfully, whether it completed its firework
+    Strike(Card),       // card misplayed
+    Drew(Player),
+    HintsChanged(u32),  // new number of hints remaining
+    LivesChanged(u32),  // new number of lives remaining
+}
+
 // represents possible settings for the game
 pub struct GameOptions {
     pub num_players: u32,
@@ -239,8 +378,45 @@ pub struct GameOptions {
     pub num_lives: u32,
     // whether to allow hints that reveal no cards
     pub allow_empty_hints: bool,
+    // whether to get a hint back when the last card is drawn
+    pub refund_hint_on_deck_out: bool,
+    // whether losing all lives scores 0, rather than the fireworks so far
+    pub zero_score_on_lives_out: bool,
 }
 
+// board contents right after a turn, as plain data, e.g. for rendering a timeline
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct BoardSnapshot {
+    pub turn: u32,
+    pub stacks: Vec<(Color, Value)>,
+    pub discard: Cards,
+    pub lives_remaining: u32,
+}
+
+// weights for turning the change in board state over a turn into a reward,
+// e.g. for reinforcement learning
+#[derive(Debug,Clone)]
+pub struct RewardShaping {
+    // reward per point scored
+    pub point: f32,
+    // penalty per life lost
+    pub strike: f32,
+    // penalty for discarding a card that was not dispensable
+    pub critical_discard: f32,
+}
+impl RewardShaping {
+    pub fn new() -> RewardShaping {
+        RewardShaping {
+            point: 1.0,
+            strike: 1.0,
+            critical_discard: 0.5,
+        }
+    }
+}
+
+// width of each cell in BoardState::render_board_aligned, and of cards in hands
+pub const BOARD_CELL_WIDTH: usize = 3;
+
 // State of everything except the player's hands
 // Is all completely common knowledge
 #[derive(Debug,Clone,Eq,PartialEq)]
@@ -262,6 +438,8 @@ pub struct BoardState {
     pub hints_total: u32,
     pub hints_remaining: u32,
     pub allow_empty_hints: bool,
+    pub refund_hint_on_deck_out: bool,
+    pub zero_score_on_lives_out: bool,
     pub lives_total: u32,
     pub lives_remaining: u32,
     // only relevant when deck runs out
@@ -283,6 +461,8 @@ impl BoardState {
             player: 0,
             turn: 1,
             allow_empty_hints: opts.allow_empty_hints,
+            refund_hint_on_deck_out: opts.refund_hint_on_deck_out,
+            zero_score_on_lives_out: opts.zero_score_on_lives_out,
             hints_total: opts.num_hints,
             hints_remaining: opts.num_hints,
             lives_total: opts.num_lives,
@@ -303,6 +483,40 @@ impl BoardState {
         self.fireworks.get(&color).unwrap()
     }
 
+    // top value of each firework (0 if empty), in color order
+    pub fn stacks(&self) -> Vec<(Color, Value)> {
+        COLORS.iter().map(|&color| (color, self.get_firework(color).top)).collect()
+    }
+
+    // snapshot after each turn taken so far, rebuilt from the turn history
+    pub fn timeline(&self) -> Vec<BoardSnapshot> {
+        let mut stacks = COLORS.iter().map(|&color| (color, 0)).collect::<Vec<_>>();
+        let mut discard = Cards::new();
+        let mut lives_remaining = self.lives_total;
+        self.turn_history.iter().enumerate().map(|(i, turn_record)| {
+            match turn_record.result {
+                TurnResult::Hint(_) => {}
+                TurnResult::Discard(ref card) => {
+                    discard.push(card.clone());
+                }
+                TurnResult::Play(ref card, true) => {
+                    let stack = stacks.iter_mut().find(|stack| stack.0 == card.color).unwrap();
+                    stack.1 = card.value;
+                }
+                TurnResult::Play(ref card, false) => {
+                    discard.push(card.clone());
+                    lives_remaining -= 1;
+                }
+            }
+            BoardSnapshot {
+                turn: (i + 1) as u32,
+                stacks: stacks.clone(),
+                discard: discard.clone(),
+                lives_remaining: lives_remaining,
+            }
+        }).collect()
+    }
+
     fn get_firework_mut(&mut self, color: Color) -> &mut Firework {
         self.fireworks.get_mut(&color).unwrap()
     }
@@ -369,6 +583,18 @@ impl BoardState {
         }
     }
 
+    // projection of the board after the given cards are played in order,
+    // skipping any that wouldn't be playable at that point
+    pub fn after_plays(&self, cards: &[Card]) -> BoardState {
+        let mut board = self.clone();
+        for card in cards {
+            if board.is_playable(card) {
+                board.get_firework_mut(card.color).place(card);
+            }
+        }
+        board
+    }
+
     pub fn get_players(&self) -> Range<Player> {
         (0..self.num_players)
     }
@@ -377,10 +603,77 @@ impl BoardState {
         self.fireworks.iter().map(|(_, firework)| firework.score()).fold(0, |a, b| a + b)
     }
 
+    // score the game counts for, once it is over
+    pub fn final_score(&self) -> Score {
+        if self.zero_score_on_lives_out && self.lives_remaining == 0 {
+            0
+        } else {
+            self.score()
+        }
+    }
+
+    // plays still missing for a perfect score
+    pub fn plays_needed(&self) -> u32 {
+        PERFECT_SCORE - self.score()
+    }
+
+    // best score still possible, given what has been discarded
+    pub fn max_achievable_score(&self) -> Score {
+        COLORS.iter().map(|&color| self.highest_attainable(color)).fold(0, |a, b| a + b)
+    }
+
+    // upper bound on the number of plays left in the game:
+    // each play before the deck runs out draws a card, then every player gets one more turn
+    pub fn max_plays_remaining(&self) -> u32 {
+        if self.deck_size > 0 {
+            self.deck_size + self.num_players
+        } else {
+            self.deckless_turns_remaining
+        }
+    }
+
+    // whether the given score is still reachable, ignoring what is in the hands
+    pub fn can_reach(&self, target: Score) -> bool {
+        let score = self.score();
+        target <= self.max_achievable_score() &&
+            (target <= score || target - score <= self.max_plays_remaining())
+    }
+
+    pub fn winnable(&self) -> bool {
+        self.can_reach(PERFECT_SCORE)
+    }
+
     pub fn discard_size(&self) -> u32 {
         self.discard.cards.len() as u32
     }
 
+    // one row per color, with fixed-width cells so columns line up:
+    // the played cards of the firework, then discarded/total for each value
+    pub fn render_board_aligned(&self) -> String {
+        let mut rows = Vec::new();
+        for &color in COLORS.iter() {
+            let top = self.get_firework(color).top;
+            let mut row = format!("{} |", color);
+            for &value in VALUES.iter() {
+                if value <= top {
+                    row.push_str(&format!(" {:>width$}", Card::new(color, value), width = BOARD_CELL_WIDTH));
+                } else {
+                    row.push_str(&format!(" {:>width$}", "..", width = BOARD_CELL_WIDTH));
+                }
+            }
+            row.push_str(" |");
+            for &value in VALUES.iter() {
+                let card = Card::new(color, value);
+                let total = get_count_for_value(value);
+                let discarded = total - self.discard.remaining(&card);
+                let cell = format!("{}/{}", discarded, total);
+                row.push_str(&format!(" {:>width$}", cell, width = BOARD_CELL_WIDTH));
+            }
+            rows.push(row);
+        }
+        rows.join("\n")
+    }
+
     pub fn player_to_left(&self, player: &Player) -> Player {
         (player + 1) % self.num_players
     }
@@ -391,6 +684,31 @@ impl BoardState {
     pub fn is_over(&self) -> bool {
         (self.lives_remaining == 0) || (self.deckless_turns_remaining == 0)
     }
+
+    // all fireworks complete
+    pub fn is_won(&self) -> bool {
+        self.score() == PERFECT_SCORE
+    }
+
+    // ran out of lives
+    pub fn is_lost(&self) -> bool {
+        self.lives_remaining == 0
+    }
+
+    // reward for the last turn, which went from prev to this state.
+    // prev must be the state exactly one turn earlier, since the last turn record is used
+    pub fn step_reward(&self, prev: &BoardState, shaping: &RewardShaping) -> f32 {
+        assert_eq!(prev.turn + 1, self.turn, "step_reward needs states one turn apart");
+        let points = self.score() as f32 - prev.score() as f32;
+        let strikes = prev.lives_remaining as f32 - self.lives_remaining as f32;
+        let mut reward = shaping.point * points - shaping.strike * strikes;
+        if let Some(&TurnRecord { result: TurnResult::Discard(ref card), .. }) = self.turn_history.last() {
+            if !prev.is_dispensable(card) {
+                reward -= shaping.critical_discard;
+            }
+        }
+        reward
+    }
 }
 impl fmt::Display for BoardState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -470,6 +788,102 @@ pub trait GameView {
             })
         })
     }
+
+    fn is_legal(&self, choice: &TurnChoice) -> bool {
+        let board = self.get_board();
+        if board.is_over() || board.player != self.me() {
+            return false;
+        }
+        match choice {
+            &TurnChoice::Play(index) | &TurnChoice::Discard(index) => {
+                index < self.my_hand_size()
+            }
+            &TurnChoice::Hint(ref hint) => {
+                let exists = match hint.hinted {
+                    Hinted::Color(color) => COLORS.contains(&color),
+                    Hinted::Value(value) => VALUES.contains(&value),
+                };
+                if !exists || board.hints_remaining == 0 || hint.player == self.me() ||
+                   hint.player >= board.num_players {
+                    return false;
+                }
+                board.allow_empty_hints || self.get_hand(&hint.player).iter().any(|card| {
+                    match hint.hinted {
+                        Hinted::Color(color) => card.color == color,
+                        Hinted::Value(value) => card.value == value,
+                    }
+                })
+            }
+        }
+    }
+
+    // Choices are numbered in a fixed way, e.g. for machine learning agents:
+    //  - [0, hand_size) plays the card at that index
+    //  - [hand_size, 2 * hand_size) discards the card at that index
+    //  - then, for each other player starting to the left, a hint for each color,
+    //    followed by a hint for each value
+    fn num_choice_indices(&self) -> usize {
+        let board = self.get_board();
+        2 * (board.hand_size as usize)
+            + (board.num_players as usize - 1) * (NUM_COLORS + NUM_VALUES)
+    }
+
+    // the number of the choice, or None if it has none: a card index past the hand size,
+    // a hint to myself or a nonexistent player, or about a nonexistent color or value
+    fn choice_index(&self, choice: &TurnChoice) -> Option<usize> {
+        let board = self.get_board();
+        let hand_size = board.hand_size as usize;
+        match choice {
+            &TurnChoice::Play(index) if index < hand_size => Some(index),
+            &TurnChoice::Discard(index) if index < hand_size => Some(hand_size + index),
+            &TurnChoice::Hint(ref hint) if hint.player != self.me() && hint.player < board.num_players => {
+                let offset = (hint.player + board.num_players - self.me() - 1) % board.num_players;
+                let hinted_index = match hint.hinted {
+                    Hinted::Color(color) => COLORS.iter().position(|&c| c == color),
+                    Hinted::Value(value) => VALUES.iter().position(|&v| v == value).map(|i| NUM_COLORS + i),
+                };
+                hinted_index.map(|hinted_index| {
+                    2 * hand_size + (offset as usize) * (NUM_COLORS + NUM_VALUES) + hinted_index
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // all legal choices, ordered by choice index
+    fn legal_choices(&self) -> Vec<TurnChoice> {
+        let board = self.get_board();
+        let mut choices = Vec::new();
+        for index in 0..self.my_hand_size() {
+            choices.push(TurnChoice::Play(index));
+        }
+        for index in 0..self.my_hand_size() {
+            choices.push(TurnChoice::Discard(index));
+        }
+        let mut player = board.player_to_left(&self.me());
+        while player != self.me() {
+            let hinteds = COLORS.iter().map(|&color| Hinted::Color(color))
+                .chain(VALUES.iter().map(|&value| Hinted::Value(value)));
+            for hinted in hinteds {
+                choices.push(TurnChoice::Hint(Hint {
+                    player: player,
+                    hinted: hinted,
+                }));
+            }
+            player = board.player_to_left(&player);
+        }
+        choices.into_iter().filter(|choice| self.is_legal(choice)).collect()
+    }
+
+    // for each choice index, whether that choice is legal
+    fn legal_choice_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; self.num_choice_indices()];
+        for choice in self.legal_choices() {
+            // legal choices always have an index
+            mask[self.choice_index(&choice).unwrap()] = true;
+        }
+        mask
+    }
 }
 
 // version of game view that is borrowed.  used in simulator for efficiency,
@@ -558,7 +972,7 @@ impl fmt::Display for GameState {
             let hand = &self.hands.get(&player).unwrap();
             try!(f.write_str(&format!("player {}:", player)));
             for card in hand.iter() {
-                try!(f.write_str(&format!("    {}", card)));
+                try!(f.write_str(&format!("  {:>width$}", card, width = BOARD_CELL_WIDTH)));
             }
             try!(f.write_str(&"\n"));
         }
@@ -570,25 +984,271 @@ impl fmt::Display for GameState {
     }
 }
 
+fn parse_u32(s: &str) -> Result<u32, String> {
+    s.parse::<u32>().map_err(|_| format!("Invalid number {:?}", s))
+}
+
+// parses "a/b"
+fn parse_fraction(s: &str) -> Result<(u32, u32), String> {
+    let parts = s.split('/').collect::<Vec<_>>();
+    if parts.len() != 2 {
+        return Err(format!("Expected a fraction, got {:?}", s));
+    }
+    Ok((parse_u32(parts[0])?, parse_u32(parts[1])?))
+}
+
+fn parse_cards(s: &str) -> Result<Cards, String> {
+    s.split_whitespace().map(|card_str| card_str.parse::<Card>()).collect()
+}
+
+fn format_cards(cards: &Cards) -> String {
+    cards.iter().map(|card| format!("{}", card)).collect::<Vec<_>>().join(" ")
+}
+
 impl GameState {
-    pub fn new(opts: &GameOptions, mut deck: Cards) -> GameState {
-        let mut board = BoardState::new(opts, deck.len() as u32);
-
-        let hands =
-            (0..opts.num_players).map(|player| {
-                let hand = (0..opts.hand_size).map(|_| {
-                    // we can assume the deck is big enough to draw initial hands
-                    board.deck_size -= 1;
-                    deck.pop().unwrap()
-                }).collect::<Vec<_>>();
-                (player, hand)
-            }).collect::<FnvHashMap<_, _>>();
+    pub fn new(opts: &GameOptions, deck: Cards) -> GameState {
+        let board = BoardState::new(opts, deck.len() as u32);
+        let hands = (0..opts.num_players).map(|player| {
+            (player, Cards::new())
+        }).collect::<FnvHashMap<_, _>>();
 
-        GameState {
+        let mut game = GameState {
             hands: hands,
             board: board,
             deck: deck,
+        };
+        for player in game.get_players() {
+            for _ in 0..opts.hand_size {
+                // we can assume the deck is big enough to draw initial hands
+                let card = game.draw_card().unwrap();
+                game.hands.get_mut(&player).unwrap().push(card);
+            }
+        }
+        game
+    }
+
+    // compact dump of the whole state, e.g. to attach to bug reports.
+    // can be loaded again with parse_dump (the turn history is not included)
+    pub fn dump(&self) -> String {
+        let board = &self.board;
+        let mut lines = Vec::new();
+        lines.push(format!("players: {}", board.num_players));
+        lines.push(format!("hand_size: {}", board.hand_size));
+        lines.push(format!("turn: {}", board.turn));
+        lines.push(format!("player: {}", board.player));
+        lines.push(format!("hints: {}/{}", board.hints_remaining, board.hints_total));
+        lines.push(format!("lives: {}/{}", board.lives_remaining, board.lives_total));
+        lines.push(format!("total_cards: {}", board.total_cards));
+        lines.push(format!("deckless_turns: {}", board.deckless_turns_remaining));
+        let mut rules = Vec::new();
+        if board.allow_empty_hints { rules.push("allow_empty_hints"); }
+        if board.refund_hint_on_deck_out { rules.push("refund_hint_on_deck_out"); }
+        if board.zero_score_on_lives_out { rules.push("zero_score_on_lives_out"); }
+        lines.push(format!("rules: {}", rules.join(" ")));
+        let fireworks = COLORS.iter().map(|&color| {
+            format!("{}{}", color, board.get_firework(color).top)
+        }).collect::<Vec<_>>();
+        lines.push(format!("fireworks: {}", fireworks.join(" ")));
+        lines.push(format!("discard: {}", format_cards(&board.discard.cards)));
+        for player in self.get_players() {
+            lines.push(format!("hand {}: {}", player, format_cards(&self.hands[&player])));
+        }
+        lines.push(format!("deck: {}", format_cards(&self.deck)));
+        lines.join("\n") + "\n"
+    }
+
+    // loads a state written by dump()
+    pub fn parse_dump(dump: &str) -> Result<GameState, String> {
+        let mut fields = FnvHashMap::default();
+        for line in dump.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parts = line.splitn(2, ':').collect::<Vec<_>>();
+            if parts.len() != 2 {
+                return Err(format!("Expected 'key: value', got {:?}", line));
+            }
+            fields.insert(parts[0].trim(), parts[1].trim());
+        }
+        let get = |key: &str| -> Result<&str, String> {
+            fields.get(key).cloned().ok_or(format!("Missing field {:?}", key))
+        };
+
+        let num_players = parse_u32(get("players")?)?;
+        let hand_size = parse_u32(get("hand_size")?)?;
+        let turn = parse_u32(get("turn")?)?;
+        let player = parse_u32(get("player")?)?;
+        let (hints_remaining, hints_total) = parse_fraction(get("hints")?)?;
+        let (lives_remaining, lives_total) = parse_fraction(get("lives")?)?;
+        let total_cards = parse_u32(get("total_cards")?)?;
+        let deckless_turns_remaining = parse_u32(get("deckless_turns")?)?;
+        let rules = get("rules")?.split_whitespace().collect::<Vec<_>>();
+        for rule in &rules {
+            match *rule {
+                "allow_empty_hints" | "refund_hint_on_deck_out" | "zero_score_on_lives_out" => {}
+                _ => { return Err(format!("Unknown rule {:?}", rule)); }
+            }
+        }
+
+        let mut fireworks = COLORS.iter().map(|&color| {
+            (color, Firework::new(color))
+        }).collect::<FnvHashMap<_, _>>();
+        for firework_str in get("fireworks")?.split_whitespace() {
+            let mut chars = firework_str.chars();
+            let color = chars.next().ok_or(String::from("Empty firework"))?;
+            let top = parse_u32(chars.as_str())?;
+            match fireworks.get_mut(&color) {
+                Some(ref mut firework) if top <= FINAL_VALUE => { firework.top = top; }
+                _ => { return Err(format!("Invalid firework {:?}", firework_str)); }
+            }
+        }
+
+        let mut discard = Discard::new();
+        for card in parse_cards(get("discard")?)? {
+            if discard.has_all(&card) {
+                return Err(format!("Too many copies of {} in discard", card));
+            }
+            discard.place(card);
+        }
+
+        let mut hands = FnvHashMap::default();
+        for player in 0..num_players {
+            hands.insert(player, parse_cards(get(&format!("hand {}", player))?)?);
+        }
+        let deck = parse_cards(get("deck")?)?;
+
+        let board = BoardState {
+            deck_size: deck.len() as u32,
+            total_cards: total_cards,
+            discard: discard,
+            fireworks: fireworks,
+            num_players: num_players,
+            turn: turn,
+            turn_history: Vec::new(),
+            player: player,
+            hand_size: hand_size,
+            hints_total: hints_total,
+            hints_remaining: hints_remaining,
+            allow_empty_hints: rules.contains(&"allow_empty_hints"),
+            refund_hint_on_deck_out: rules.contains(&"refund_hint_on_deck_out"),
+            zero_score_on_lives_out: rules.contains(&"zero_score_on_lives_out"),
+            lives_total: lives_total,
+            lives_remaining: lives_remaining,
+            deckless_turns_remaining: deckless_turns_remaining,
+        };
+        let game = GameState {
+            hands: hands,
+            board: board,
+            deck: deck,
+        };
+        game.validate()?;
+        Ok(game)
+    }
+
+    // checks that the state is one that could come up in a game, so that playing on from it
+    // can't go wrong: every card is somewhere exactly once, hands have the right sizes, etc.
+    pub fn validate(&self) -> Result<(), String> {
+        let board = &self.board;
+        let num_players = board.num_players;
+        let hand_size = board.hand_size;
+        let turn = board.turn;
+        let player = board.player;
+        if num_players == 0 || player >= num_players {
+            return Err(format!("Invalid player {} of {}", player, num_players));
+        }
+        if hand_size == 0 {
+            return Err(String::from("Invalid hand size 0"));
+        }
+        if turn == 0 || board.hints_remaining > board.hints_total ||
+           board.lives_remaining > board.lives_total {
+            return Err(String::from("Invalid turn, hints or lives"));
+        }
+        if (turn - 1) % num_players != player {
+            return Err(format!("Player {} cannot be on turn {}", player, turn));
+        }
+        if board.deck_size != self.deck.len() as u32 {
+            return Err(format!("Deck size is {}, but {} cards are left", board.deck_size, self.deck.len()));
+        }
+
+        // once the deck runs out, each player gets exactly one more turn
+        let deckless_turns_ok = if self.deck.is_empty() {
+            board.deckless_turns_remaining <= num_players
+        } else {
+            num_players.checked_add(1) == Some(board.deckless_turns_remaining)
+        };
+        if !deckless_turns_ok {
+            return Err(format!("Invalid deckless turns {}", board.deckless_turns_remaining));
+        }
+        // each play or discard uses up a card or a deckless turn and regains at most one hint,
+        // which bounds the turns left; the turn counter must not overflow before they are over
+        let max_plays_and_discards = self.deck.len() as u64 + num_players as u64 + 1;
+        // (one more hint may be refunded when the deck runs out)
+        let max_turns_left = 2 * max_plays_and_discards + board.hints_remaining as u64 + 1;
+        if turn as u64 + max_turns_left > u32::max_value() as u64 {
+            return Err(format!("Invalid turn {}", turn));
+        }
+
+        // hands are full until the deck runs out, and can only have shrunk for players
+        // who already took their last turn.  the current player has not, unless the game is over
+        if self.hands.len() as u32 != num_players {
+            return Err(format!("Expected {} hands, got {}", num_players, self.hands.len()));
+        }
+        for owner in 0..num_players {
+            let hand = self.hands.get(&owner).ok_or(format!("Missing hand of player {}", owner))?;
+            let size = hand.len() as u32;
+            let took_last_turn = owner != player || board.deckless_turns_remaining == 0;
+            let min_size = if self.deck.is_empty() && took_last_turn { hand_size - 1 } else { hand_size };
+            if size < min_size || size > hand_size {
+                return Err(format!("Hand of player {} has the wrong size", owner));
+            }
+        }
+
+        // every copy of every card has to be somewhere exactly once,
+        // otherwise later turns could run the counts out of range
+        let mut counts = FnvHashMap::default();
+        for &color in COLORS.iter() {
+            let top = board.fireworks.get(&color).ok_or(format!("Missing firework {}", color))?.top;
+            if top > FINAL_VALUE {
+                return Err(format!("Invalid firework {}{}", color, top));
+            }
+            for value in 1..(top + 1) {
+                *counts.entry(Card::new(color, value)).or_insert(0) += 1;
+            }
         }
+        let held = self.hands.values().flat_map(|hand| hand.iter());
+        for card in board.discard.cards.iter().chain(held).chain(self.deck.iter()) {
+            *counts.entry(card.clone()).or_insert(0) += 1;
+        }
+        let mut num_cards = 0;
+        for &color in COLORS.iter() {
+            for &value in VALUES.iter() {
+                let card = Card::new(color, value);
+                if counts.remove(&card) != Some(get_count_for_value(value)) {
+                    return Err(format!("Wrong number of copies of {}", card));
+                }
+                num_cards += get_count_for_value(value);
+            }
+        }
+        if let Some(card) = counts.keys().next() {
+            return Err(format!("Invalid card {}", card));
+        }
+        if board.total_cards != num_cards {
+            return Err(format!("Expected {} total cards, got {}", num_cards, board.total_cards));
+        }
+        Ok(())
+    }
+
+    // number of cards left to draw
+    pub fn cards_remaining(&self) -> u32 {
+        debug_assert_eq!(self.deck.len() as u32, self.board.deck_size);
+        self.board.deck_size
+    }
+
+    // the only place cards leave the deck, so the board's deck size stays in sync
+    fn draw_card(&mut self) -> Option<Card> {
+        let card = self.deck.pop();
+        self.board.deck_size = self.deck.len() as u32;
+        card
     }
 
     pub fn get_players(&self) -> Range<Player> {
@@ -599,10 +1259,30 @@ impl GameState {
         self.board.is_over()
     }
 
+    pub fn is_won(&self) -> bool {
+        self.board.is_won()
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.board.is_lost()
+    }
+
     pub fn score(&self) -> Score {
         self.board.score()
     }
 
+    pub fn final_score(&self) -> Score {
+        self.board.final_score()
+    }
+
+    pub fn plays_needed(&self) -> u32 {
+        self.board.plays_needed()
+    }
+
+    pub fn winnable(&self) -> bool {
+        self.board.winnable()
+    }
+
     // get the game state view of a particular player
     pub fn get_view(&self, player: Player) -> BorrowedGameView {
         let mut other_hands = FnvHashMap::default();
@@ -626,12 +1306,15 @@ impl GameState {
     }
 
     fn replenish_hand(&mut self) {
-        let ref mut hand = self.hands.get_mut(&self.board.player).unwrap();
-        if (hand.len() as u32) < self.board.hand_size {
-            if let Some(new_card) = self.deck.pop() {
-                self.board.deck_size -= 1;
+        let player = self.board.player;
+        if (self.hands[&player].len() as u32) < self.board.hand_size {
+            if let Some(new_card) = self.draw_card() {
                 debug!("Drew new card, {}", new_card);
-                hand.push(new_card);
+                self.hands.get_mut(&player).unwrap().push(new_card);
+                if self.board.deck_size == 0 && self.board.refund_hint_on_deck_out {
+                    debug!("Deck ran out, refunding a hint");
+                    self.board.try_add_hint();
+                }
             }
         }
     }
@@ -711,7 +1394,7 @@ impl GameState {
 
         self.replenish_hand();
 
-        if self.board.deck_size == 0 {
+        if self.cards_remaining() == 0 {
             self.board.deckless_turns_remaining -= 1;
         }
         self.board.turn += 1;
@@ -723,4 +1406,784 @@ impl GameState {
 
         turn_record
     }
+
+    // processes a sequence of choices, e.g. to replay a recorded game.
+    // stops at the first illegal choice and returns its index,
+    // leaving the game as it was after the choices before it
+    pub fn process_choices(&mut self, choices: Vec<TurnChoice>) -> Result<Vec<TurnRecord>, usize> {
+        let mut turn_records = Vec::new();
+        for (i, choice) in choices.into_iter().enumerate() {
+            let legal = {
+                let player = self.board.player;
+                self.get_view(player).is_legal(&choice)
+            };
+            if !legal {
+                return Err(i);
+            }
+            turn_records.push(self.process_choice(choice));
+        }
+        Ok(turn_records)
+    }
+
+    // like process_choice, but also lists everything that happened
+    pub fn process_choice_with_events(&mut self, choice: TurnChoice) -> (TurnRecord, Vec<GameEvent>) {
+        let hints_before = self.board.hints_remaining;
+        let lives_before = self.board.lives_remaining;
+        let deck_size_before = self.board.deck_size;
+
+        let turn_record = self.process_choice(choice);
+
+        let mut events = Vec::new();
+        match (&turn_record.choice, &turn_record.result) {
+            (&TurnChoice::Hint(ref hint), &TurnResult::Hint(ref matches)) => {
+                events.push(GameEvent::Hinted(hint.clone(), matches.clone()));
+            }
+            (_, &TurnResult::Discard(ref card)) => {
+                events.push(GameEvent::Discarded(card.clone()));
+            }
+            (_, &TurnResult::Play(ref card, true)) => {
+                events.push(GameEvent::Played(card.clone(), card.value == FINAL_VALUE));
+            }
+            (_, &TurnResult::Play(ref card, false)) => {
+                events.push(GameEvent::Strike(card.clone()));
+            }
+            _ => {
+                panic!("Got turn choice {:?}, but turn result {:?}",
+                       turn_record.choice, turn_record.result);
+            }
+        }
+        if self.board.deck_size < deck_size_before {
+            events.push(GameEvent::Drew(turn_record.player));
+        }
+        if self.board.hints_remaining != hints_before {
+            events.push(GameEvent::HintsChanged(self.board.hints_remaining));
+        }
+        if self.board.lives_remaining != lives_before {
+            events.push(GameEvent::LivesChanged(self.board.lives_remaining));
+        }
+        (turn_record, events)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::{ChaChaRng, Rng, SeedableRng};
+
+    use super::*;
+    use simulator::{new_deck, simulate_once};
+    use strategy::GameStrategyConfig;
+    use strategies::cheating::CheatingStrategyConfig;
+    use strategies::examples::UniformRandomStrategyConfig;
+
+    pub fn test_opts(num_players: u32) -> GameOptions {
+        GameOptions {
+            num_players: num_players,
+            hand_size: if num_players <= 3 { 5 } else { 4 },
+            num_hints: 8,
+            num_lives: 3,
+            allow_empty_hints: false,
+            refund_hint_on_deck_out: false,
+            zero_score_on_lives_out: false,
+        }
+    }
+
+    // a game whose players are dealt the given hands, e.g. "r1 r2 y1 b5 w3", in slot order.
+    // the rest of the cards form the deck, in order of color and value
+    pub fn deal(opts: &GameOptions, hands: &[&str]) -> GameState {
+        let mut deck = full_deck();
+        let mut dealt = Cards::new();
+        for hand in hands {
+            for card in parse_cards(hand).unwrap() {
+                let index = deck.iter().position(|other| *other == card).expect("no copy left to deal");
+                dealt.push(deck.remove(index));
+            }
+        }
+        // cards are drawn from the end of the deck
+        dealt.reverse();
+        deck.extend(dealt);
+        let game = GameState::new(opts, deck);
+        for (player, hand) in hands.iter().enumerate() {
+            assert_eq!(format_cards(game.hands.get(&(player as Player)).unwrap()), *hand);
+        }
+        game
+    }
+
+    // dumps of every state of a seeded game played by the cheating strategy, from the deal
+    // to the final state, obtained by replaying its history on the same deck
+    pub fn simulated_dumps(num_players: u32, seed: u32) -> Vec<String> {
+        let opts = test_opts(num_players);
+        let finished = simulate_once(&opts, CheatingStrategyConfig::new().initialize(&opts), seed);
+        let mut game = GameState::new(&opts, new_deck(&mut ChaChaRng::from_seed(&[seed])));
+        let mut dumps = vec![game.dump()];
+        for turn_record in &finished.board.turn_history {
+            game.process_choice(turn_record.choice.clone());
+            dumps.push(game.dump());
+        }
+        assert_eq!(game.dump(), finished.dump());
+        dumps
+    }
+
+    // plays random legal choices until the game ends
+    fn play_out(game: &mut GameState, rng: &mut ChaChaRng) {
+        while !game.is_over() {
+            let choices = game.get_view(game.board.player).legal_choices();
+            let choice = rng.choose(&choices).cloned().expect("no legal choice");
+            game.process_choice(choice);
+        }
+    }
+
+    // parsing must never panic, and anything accepted must be playable to the end
+    fn check_parse(text: &str, rng: &mut ChaChaRng) {
+        if let Ok(mut game) = GameState::parse_dump(text) {
+            play_out(&mut game, rng);
+        }
+    }
+
+    // the dump with the given field set to a new value
+    pub fn replace_field(dump: &str, key: &str, value: &str) -> String {
+        dump.lines().map(|line| {
+            if line.starts_with(&format!("{}:", key)) { format!("{}: {}", key, value) } else { line.to_string() }
+        }).collect::<Vec<_>>().join("\n")
+    }
+
+    // characters that make up dumps, so that edits are likely to get past the first checks
+    const DUMP_CHARS: &[u8] = b"0123456789rygbw: /\n";
+
+    fn mutate(dump: &str, rng: &mut ChaChaRng) -> String {
+        let mut bytes = dump.as_bytes().to_vec();
+        for _ in 0..rng.gen_range(1, 4) {
+            if bytes.is_empty() {
+                break;
+            }
+            let i = rng.gen_range(0, bytes.len());
+            let c = *rng.choose(DUMP_CHARS).unwrap();
+            match rng.gen_range(0, 4) {
+                0 => { bytes[i] = rng.gen(); }
+                1 => { bytes[i] = c; }
+                2 => { bytes.insert(i, c); }
+                _ => { bytes.remove(i); }
+            }
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    // every choice the current player could name, legal or not, for a full hand
+    fn all_choices(game: &GameState) -> Vec<TurnChoice> {
+        let board = &game.board;
+        let mut choices = Vec::new();
+        for index in 0..board.hand_size as usize {
+            choices.push(TurnChoice::Play(index));
+            choices.push(TurnChoice::Discard(index));
+        }
+        for player in game.get_players().filter(|&player| player != board.player) {
+            for &color in COLORS.iter() {
+                choices.push(hint(player, Hinted::Color(color)));
+            }
+            for &value in VALUES.iter() {
+                choices.push(hint(player, Hinted::Value(value)));
+            }
+        }
+        choices
+    }
+
+    // reward for the given choice, made by the current player of the game
+    fn reward_for(game: &mut GameState, choice: TurnChoice) -> f32 {
+        let prev = game.board.clone();
+        game.process_choice(choice);
+        game.board.step_reward(&prev, &RewardShaping::new())
+    }
+
+    // final score of the given choices, played from the same deal, with and without zeroing
+    // the score when the lives run out
+    fn scores_under_both_rulesets(choices: &[TurnChoice]) -> (Score, Score) {
+        let score = |zero_score_on_lives_out| {
+            let mut opts = test_opts(2);
+            opts.zero_score_on_lives_out = zero_score_on_lives_out;
+            let mut game = deal(&opts, &["r1 r2 r3 y3 y4", "y1 g2 g3 b2 b3"]);
+            game.process_choices(choices.to_vec()).unwrap();
+            assert!(game.is_over());
+            game.final_score()
+        };
+        (score(false), score(true))
+    }
+
+    // (cards left in the deck, hints remaining) after each turn of a seeded game that starts by
+    // spending `initial_hints` hints, then alternates between discarding and hinting
+    fn hints_by_turn(refund_hint_on_deck_out: bool, initial_hints: usize) -> Vec<(u32, u32)> {
+        let mut opts = test_opts(2);
+        opts.refund_hint_on_deck_out = refund_hint_on_deck_out;
+        let mut game = GameState::new(&opts, new_deck(&mut ChaChaRng::from_seed(&[0])));
+        let mut turns = Vec::new();
+        while !game.is_over() {
+            let turn = turns.len();
+            let choice = if turn < initial_hints || turn % 2 == 1 {
+                let other = game.board.player_to_left(&game.board.player);
+                hint(other, Hinted::Color(game.hands[&other][0].color))
+            } else {
+                TurnChoice::Discard(0)
+            };
+            game.process_choice(choice);
+            assert!(game.board.hints_remaining <= game.board.hints_total);
+            turns.push((game.board.deck_size, game.board.hints_remaining));
+        }
+        turns
+    }
+
+    #[test]
+    fn deck_out_refunds_one_hint_only_with_rule() {
+        let with_refund = hints_by_turn(true, 4);
+        let without_refund = hints_by_turn(false, 4);
+        assert_eq!(with_refund.len(), without_refund.len());
+        let deck_out = with_refund.iter().position(|&(deck_size, _)| deck_size == 0).unwrap();
+        for (turn, (&(_, with), &(_, without))) in with_refund.iter().zip(without_refund.iter()).enumerate() {
+            if turn < deck_out {
+                assert_eq!(with, without, "turn {}", turn);
+            } else {
+                assert_eq!(with, without + 1, "turn {}", turn);
+            }
+        }
+
+        // when the last card is drawn with all hints available, the refund is dropped rather than
+        // exceeding the total, which hints_by_turn checks after every turn
+        let full = hints_by_turn(true, 0);
+        let deck_out = full.iter().position(|&(deck_size, _)| deck_size == 0).unwrap();
+        assert_eq!(full[deck_out].1, 8);
+    }
+
+    #[test]
+    fn lost_five_caps_reachable_score() {
+        let opts = test_opts(2);
+        let mut game = deal(&opts, &["r5 y2 g3 b4 w5", "r1 r2 r3 r4 y1"]);
+        assert!(game.board.winnable());
+        game.process_choice(TurnChoice::Discard(0));
+        game.process_choice(TurnChoice::Play(0));
+
+        let board = &game.board;
+        assert_eq!(board.max_achievable_score(), PERFECT_SCORE - 1);
+        assert_eq!(board.plays_needed(), PERFECT_SCORE - 1);
+        assert!(!board.winnable());
+        assert!(!board.can_reach(PERFECT_SCORE));
+        assert!(board.can_reach(PERFECT_SCORE - 1));
+        assert!(board.can_reach(board.score()));
+    }
+
+    #[test]
+    fn lives_out_scores_partial_stacks_only_in_casual_rules() {
+        // r1, y1, r2 are played, then three misplays
+        let choices = vec![
+            TurnChoice::Play(0), TurnChoice::Play(0), TurnChoice::Play(0),
+            TurnChoice::Play(1), TurnChoice::Play(1), TurnChoice::Play(1),
+        ];
+        assert_eq!(scores_under_both_rulesets(&choices), (3, 0));
+    }
+
+    #[test]
+    fn deck_out_scores_stacks_under_both_rules() {
+        // r1, y1, r2 are played, then both players discard until the deck runs out
+        let mut choices = vec![TurnChoice::Play(0), TurnChoice::Play(0), TurnChoice::Play(0)];
+        let deck_size = 50 - 2 * 5;
+        for _ in 0..(deck_size - 3 + 2) {
+            choices.push(TurnChoice::Discard(4));
+        }
+        assert_eq!(scores_under_both_rulesets(&choices), (3, 3));
+    }
+
+    #[test]
+    fn drawn_and_remaining_cards_add_up_to_deck() {
+        for num_players in 2..6 {
+            for seed in 0..5 {
+                let opts = test_opts(num_players);
+                let deck = new_deck(&mut ChaChaRng::from_seed(&[seed]));
+                let deck_size = deck.len() as u32;
+                let mut game = GameState::new(&opts, deck);
+                let mut rng = ChaChaRng::from_seed(&[seed]);
+                let mut drawn = num_players * opts.hand_size;
+                while !game.is_over() {
+                    assert_eq!(drawn + game.cards_remaining(), deck_size);
+                    game.validate().unwrap();
+
+                    let remaining_before = game.cards_remaining();
+                    let choices = game.get_view(game.board.player).legal_choices();
+                    let choice = rng.choose(&choices).cloned().unwrap();
+                    let draws = match choice {
+                        TurnChoice::Hint(_) => false,
+                        TurnChoice::Play(_) | TurnChoice::Discard(_) => remaining_before > 0,
+                    };
+                    game.process_choice(choice);
+                    if draws {
+                        drawn += 1;
+                        assert_eq!(game.cards_remaining(), remaining_before - 1);
+                    } else {
+                        assert_eq!(game.cards_remaining(), remaining_before);
+                    }
+                }
+                assert_eq!(drawn + game.cards_remaining(), deck_size);
+                game.validate().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn aligned_rows_have_the_same_length() {
+        for dump in simulated_dumps(3, 0) {
+            let game = GameState::parse_dump(&dump).unwrap();
+            let rendered = game.board.render_board_aligned();
+            let rows = rendered.lines().collect::<Vec<_>>();
+            assert_eq!(rows.len(), NUM_COLORS);
+            assert!(rows.iter().all(|row| row.len() == rows[0].len()), "{}", rendered);
+        }
+
+        let game = deal(&test_opts(3), &["r1 y2 g3 b4 w5", "r1 r2 r3 r4 r5", "b1 b1 y1 y1 g1"]);
+        let rendered = format!("{}", game);
+        let hand_rows = rendered.lines().filter(|line| line.starts_with("player ")).collect::<Vec<_>>();
+        assert_eq!(hand_rows.len(), 3);
+        assert!(hand_rows.iter().all(|row| row.len() == hand_rows[0].len()), "{}", rendered);
+    }
+
+    #[test]
+    fn completing_firework_reports_play_and_hint_refund() {
+        let opts = test_opts(2);
+        let mut game = deal(&opts, &["y1 y2 y3 y4 y5", "r1 r2 r3 r4 r5"]);
+        for value in 1..FINAL_VALUE {
+            game.process_choice(hint(1, Hinted::Color('r')));
+            let (_, events) = game.process_choice_with_events(TurnChoice::Play(0));
+            assert_eq!(events, vec![GameEvent::Played(Card::new('r', value), false), GameEvent::Drew(1)]);
+        }
+        game.process_choice(hint(1, Hinted::Color('r')));
+        assert_eq!(game.board.hints_remaining, 3);
+        let (turn_record, events) = game.process_choice_with_events(TurnChoice::Play(0));
+        assert_eq!(turn_record.result, TurnResult::Play(Card::new('r', 5), true));
+        assert_eq!(events, vec![
+            GameEvent::Played(Card::new('r', 5), true),
+            GameEvent::Drew(1),
+            GameEvent::HintsChanged(4),
+        ]);
+    }
+
+    #[test]
+    fn hint_set_deduplicates_hints() {
+        let mut hints = HintSet::new();
+        assert!(hints.insert(Hint { player: 1, hinted: Hinted::Color('r') }));
+        assert!(hints.insert(Hint { player: 1, hinted: Hinted::Value(1) }));
+        // the same color or value to another player is a different hint
+        assert!(hints.insert(Hint { player: 2, hinted: Hinted::Color('r') }));
+        assert!(hints.insert(Hint { player: 2, hinted: Hinted::Value(1) }));
+        assert_eq!(hints.len(), 4);
+
+        assert!(!hints.insert(Hint { player: 1, hinted: Hinted::Color('r') }));
+        assert!(!hints.insert(Hint { player: 2, hinted: Hinted::Value(1) }));
+        assert_eq!(hints.len(), 4);
+
+        assert!(hints.contains(&Hint { player: 1, hinted: Hinted::Value(1) }));
+        assert!(!hints.contains(&Hint { player: 1, hinted: Hinted::Value(2) }));
+        assert!(!hints.contains(&Hint { player: 0, hinted: Hinted::Color('r') }));
+    }
+
+    #[test]
+    fn step_reward_scores_plays_misplays_and_discards() {
+        let opts = test_opts(2);
+        let mut game = deal(&opts, &["r1 y2 g5 b1 w1", "r1 r2 r3 r4 r5"]);
+        // player 0 plays r1, player 1 misplays r3
+        assert_eq!(reward_for(&mut game, TurnChoice::Play(0)), 1.0);
+        assert_eq!(reward_for(&mut game, TurnChoice::Play(2)), -1.0);
+        // critical: the only g5, while g can still be completed
+        assert_eq!(reward_for(&mut game, TurnChoice::Discard(1)), -0.5);
+        // safe: a copy of r1 is already played
+        assert_eq!(reward_for(&mut game, TurnChoice::Discard(0)), 0.0);
+        // misplaying y2 is only a strike, since another copy is left
+        assert_eq!(reward_for(&mut game, TurnChoice::Play(0)), -1.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "one turn apart")]
+    fn step_reward_rejects_states_further_apart() {
+        let opts = test_opts(2);
+        let mut game = deal(&opts, &["r1 y2 g5 b1 w1", "r1 r2 r3 r4 r5"]);
+        let prev = game.board.clone();
+        game.process_choice(TurnChoice::Play(0));
+        game.process_choice(TurnChoice::Play(0));
+        game.board.step_reward(&prev, &RewardShaping::new());
+    }
+
+    #[test]
+    fn legal_choice_mask_matches_legal_choices() {
+        for num_players in 2..6 {
+            for seed in 0..3 {
+                for dump in simulated_dumps(num_players, seed) {
+                    let game = GameState::parse_dump(&dump).unwrap();
+                    if game.is_over() {
+                        continue;
+                    }
+                    let view = game.get_view(game.board.player);
+                    let choices = all_choices(&game);
+
+                    // choice_index is a bijection from all choices onto 0..num_choice_indices()
+                    let mut indices = choices.iter().map(|choice| view.choice_index(choice).unwrap()).collect::<Vec<_>>();
+                    indices.sort();
+                    assert_eq!(indices, (0..view.num_choice_indices()).collect::<Vec<_>>());
+
+                    // the mask is true exactly at the legal choices
+                    let mask = view.legal_choice_mask();
+                    assert_eq!(mask.len(), view.num_choice_indices());
+                    let legal = view.legal_choices();
+                    for choice in &choices {
+                        let is_legal = legal.contains(choice);
+                        assert_eq!(is_legal, view.is_legal(choice), "{:?}\n{}", choice, dump);
+                        assert_eq!(mask[view.choice_index(choice).unwrap()], is_legal, "{:?}\n{}", choice, dump);
+                    }
+                    assert_eq!(mask.iter().filter(|&&legal| legal).count(), legal.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dump_round_trips_through_parse_dump() {
+        for num_players in 2..6 {
+            for seed in 0..5 {
+                for dump in simulated_dumps(num_players, seed) {
+                    let game = GameState::parse_dump(&dump).unwrap_or_else(|err| panic!("{}\n{}", err, dump));
+                    assert_eq!(game.dump(), dump);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_dump_loads_finished_deck_out_game() {
+        let dumps = simulated_dumps(3, 0);
+        let dump = dumps.last().unwrap();
+        let game = GameState::parse_dump(dump).unwrap();
+        assert!(game.is_over());
+        assert_eq!(game.board.deckless_turns_remaining, 0);
+        assert_eq!(&game.dump(), dump);
+    }
+
+    #[test]
+    fn parse_dump_rejects_oversized_numbers() {
+        let dump = simulated_dumps(2, 0).remove(0);
+        let replace = |key: &str, value: &str| replace_field(&dump, key, value);
+        let oversized = vec![
+            replace("players", "4294967295"),
+            replace("players", "4294967296"),
+            replace("hand_size", "4294967295"),
+            replace("turn", "4294967295"),
+            replace("hints", "4294967295/4294967295"),
+            replace("lives", "99999999999999999999/3"),
+            replace("total_cards", "4294967295"),
+            replace("deckless_turns", "4294967295"),
+            replace("fireworks", "r4294967295"),
+            replace("discard", &vec!["r1"; 10000].join(" ")),
+        ];
+        for text in oversized {
+            assert!(GameState::parse_dump(&text).is_err(), "accepted {}", text);
+        }
+    }
+
+    #[test]
+    fn parse_dump_handles_truncated_input() {
+        let mut rng = ChaChaRng::from_seed(&[0]);
+        for dump in simulated_dumps(3, 1).iter().step_by(10) {
+            for (i, _) in dump.char_indices() {
+                check_parse(&dump[..i], &mut rng);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_dump_handles_mutated_input() {
+        let mut rng = ChaChaRng::from_seed(&[0]);
+        for num_players in 2..6 {
+            for seed in 0..3 {
+                for dump in simulated_dumps(num_players, seed) {
+                    for _ in 0..5 {
+                        let text = mutate(&dump, &mut rng);
+                        check_parse(&text, &mut rng);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parsers_handle_random_bytes() {
+        let mut rng = ChaChaRng::from_seed(&[0]);
+        for _ in 0..2000 {
+            let len = rng.gen_range(0, 64);
+            let bytes = (0..len).map(|_| {
+                if rng.gen() { rng.gen() } else { *rng.choose(DUMP_CHARS).unwrap() }
+            }).collect::<Vec<u8>>();
+            let text = String::from_utf8_lossy(&bytes).into_owned();
+            check_parse(&text, &mut rng);
+            let _ = text.parse::<Card>();
+            let _ = text.parse::<TurnChoice>();
+        }
+    }
+
+    #[test]
+    fn turn_choice_parses_its_display() {
+        let dumps = simulated_dumps(4, 0);
+        for dump in dumps.iter().step_by(7) {
+            let game = GameState::parse_dump(dump).unwrap();
+            for choice in game.get_view(game.board.player).legal_choices() {
+                assert_eq!(format!("{}", choice).parse::<TurnChoice>(), Ok(choice));
+            }
+        }
+        for text in &["", "play", "play -1", "play 99999999999999999999", "discard 1 2",
+                      "hint 1", "hint 1 x", "hint 1 6", "hint 1 rr", "hint x r", "pass 0"] {
+            assert!(text.parse::<TurnChoice>().is_err(), "accepted {:?}", text);
+        }
+    }
+
+    fn hint(player: Player, hinted: Hinted) -> TurnChoice {
+        TurnChoice::Hint(Hint {
+            player: player,
+            hinted: hinted,
+        })
+    }
+
+    #[test]
+    fn process_choices_rejects_malformed_choices() {
+        let start = simulated_dumps(3, 0).remove(0);
+        let permissive = replace_field(&start, "rules", "allow_empty_hints");
+        let no_hints = replace_field(&start, "hints", "0/8");
+        let cases = vec![
+            (&start, TurnChoice::Play(5)),
+            (&start, TurnChoice::Discard(5)),
+            (&start, TurnChoice::Play(usize::max_value())),
+            (&start, hint(1, Hinted::Value(1))),
+            (&start, hint(3, Hinted::Value(1))),
+            (&start, hint(Player::max_value(), Hinted::Value(1))),
+            (&permissive, hint(2, Hinted::Color('x'))),
+            (&permissive, hint(2, Hinted::Value(0))),
+            (&permissive, hint(2, Hinted::Value(9))),
+            (&no_hints, hint(2, Hinted::Value(1))),
+        ];
+        for (dump, choice) in cases {
+            let mut game = GameState::parse_dump(dump).unwrap();
+            // player 0 plays first, so that the rejected choice, by player 1, is not the first one
+            let result = game.process_choices(vec![TurnChoice::Play(0), choice.clone()]);
+            assert_eq!(result.map(|records| records.len()), Err(1), "accepted {:?}", choice);
+            assert_eq!(game.board.turn, 2);
+            assert_eq!(game.board.turn_history.len(), 1);
+        }
+    }
+
+    #[test]
+    fn process_choices_rejects_empty_hints_unless_allowed() {
+        let start = simulated_dumps(3, 0).remove(0);
+        let game = GameState::parse_dump(&start).unwrap();
+        let missing = COLORS.iter().cloned().find(|&color| {
+            game.hands[&1].iter().all(|card| card.color != color)
+        });
+        if let Some(color) = missing {
+            let mut game = GameState::parse_dump(&start).unwrap();
+            assert_eq!(game.process_choices(vec![hint(1, Hinted::Color(color))]).map(|_| ()), Err(0));
+            let mut game = GameState::parse_dump(&replace_field(&start, "rules", "allow_empty_hints")).unwrap();
+            assert!(game.process_choices(vec![hint(1, Hinted::Color(color))]).is_ok());
+        }
+    }
+
+    #[test]
+    fn process_choices_rejects_choices_after_game_over() {
+        let end = simulated_dumps(3, 0).pop().unwrap();
+        let mut game = GameState::parse_dump(&end).unwrap();
+        assert_eq!(game.process_choices(vec![TurnChoice::Play(0)]).map(|_| ()), Err(0));
+        assert_eq!(game.dump(), end);
+    }
+
+    #[test]
+    fn parse_dump_rejects_malformed_dumps() {
+        let dump = simulated_dumps(3, 0).remove(10);
+        let without = |key: &str| {
+            dump.lines().filter(|line| !line.starts_with(&format!("{}:", key))).collect::<Vec<_>>().join("\n")
+        };
+        let replace = |key: &str, value: &str| replace_field(&dump, key, value);
+        let malformed = vec![
+            String::new(),
+            String::from("no colon here"),
+            without("players"),
+            without("deck"),
+            without("hand 2"),
+            replace("players", "-1"),
+            replace("players", "0"),
+            replace("player", "3"),
+            replace("player", "2"),
+            replace("turn", "0"),
+            replace("hand_size", "0"),
+            replace("hints", "8"),
+            replace("hints", "9/8"),
+            replace("lives", "1/2/3"),
+            replace("rules", "no_such_rule"),
+            replace("fireworks", "r6"),
+            replace("fireworks", "x1"),
+            replace("fireworks", "r"),
+            replace("discard", "r6"),
+            replace("discard", "x1"),
+            replace("discard", "r1 r1 r1 r1"),
+            replace("hand 0", "r1"),
+            replace("deck", ""),
+            replace("total_cards", "49"),
+        ];
+        for text in malformed {
+            assert!(GameState::parse_dump(&text).is_err(), "accepted {}", text);
+        }
+    }
+
+    #[test]
+    fn timeline_matches_board_after_every_turn() {
+        for num_players in 2..6 {
+            for seed in 0..5 {
+                let opts = test_opts(num_players);
+                let strategies = vec![
+                    CheatingStrategyConfig::new().initialize(&opts),
+                    UniformRandomStrategyConfig { seed: seed }.initialize(&opts),
+                ];
+                for strategy in strategies {
+                    let finished = simulate_once(&opts, strategy, seed);
+                    let board = &finished.board;
+                    let timeline = board.timeline();
+                    assert_eq!(timeline.len(), board.turn_history.len());
+                    let last = timeline.last().unwrap();
+                    assert_eq!(last.stacks, board.stacks());
+                    assert_eq!(last.discard, board.discard.cards);
+                    assert_eq!(last.lives_remaining, board.lives_remaining);
+
+                    // every snapshot matches the board replayed up to that turn
+                    let mut game = GameState::new(&opts, new_deck(&mut ChaChaRng::from_seed(&[seed])));
+                    for (turn_record, snapshot) in board.turn_history.iter().zip(timeline.iter()) {
+                        game.process_choice(turn_record.choice.clone());
+                        assert_eq!(snapshot.turn, game.board.turn - 1);
+                        assert_eq!(snapshot.stacks, game.board.stacks());
+                        assert_eq!(snapshot.discard, game.board.discard.cards);
+                        assert_eq!(snapshot.lives_remaining, game.board.lives_remaining);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn criticality_report_lists_discarded_copies() {
+        let mut discard = Discard::new();
+        for card in parse_cards("r3 r4 r3 b1").unwrap() {
+            discard.place(card);
+        }
+        assert_eq!(discard.criticality_report(), vec![
+            ('r', vec![(3, 2, 2), (4, 1, 2)]),
+            ('y', vec![]),
+            ('g', vec![]),
+            ('b', vec![(1, 1, 3)]),
+            ('w', vec![]),
+        ]);
+    }
+
+    #[test]
+    fn terminal_states_are_won_lost_or_neither() {
+        // a perfect game
+        let opts = test_opts(4);
+        let won = simulate_once(&opts, CheatingStrategyConfig::new().initialize(&opts), 0);
+        assert_eq!(won.score(), PERFECT_SCORE);
+        assert!(won.is_over() && won.is_won() && !won.is_lost());
+
+        // three misplays
+        let opts = test_opts(2);
+        let mut lost = deal(&opts, &["r1 r2 r3 y3 y4", "y1 g2 g3 b2 b3"]);
+        lost.process_choices(vec![
+            TurnChoice::Play(0), TurnChoice::Play(1), TurnChoice::Play(1),
+        ]).unwrap();
+        assert!(!lost.is_over());
+        lost.process_choice(TurnChoice::Play(1));
+        assert_eq!(lost.board.lives_remaining, 0);
+        assert!(lost.is_over() && lost.is_lost() && !lost.is_won());
+
+        // the deck runs out, and every player takes a last turn
+        let mut deck_out = deal(&opts, &["r1 r2 r3 y3 y4", "y1 g2 g3 b2 b3"]);
+        while deck_out.board.deck_size > 0 {
+            deck_out.process_choice(TurnChoice::Discard(0));
+        }
+        for _ in 0..opts.num_players {
+            assert!(!deck_out.is_over());
+            deck_out.process_choice(TurnChoice::Discard(0));
+        }
+        assert!(deck_out.is_over() && !deck_out.is_won() && !deck_out.is_lost());
+    }
+
+    #[test]
+    fn process_choices_replays_recorded_games() {
+        for num_players in 2..6 {
+            for seed in 0..5 {
+                let opts = test_opts(num_players);
+                let finished = simulate_once(&opts, CheatingStrategyConfig::new().initialize(&opts), seed);
+                let choices = finished.board.turn_history.iter().map(|turn_record| {
+                    turn_record.choice.clone()
+                }).collect::<Vec<_>>();
+                let mut game = GameState::new(&opts, new_deck(&mut ChaChaRng::from_seed(&[seed])));
+                let turn_records = game.process_choices(choices).unwrap();
+                assert_eq!(turn_records, finished.board.turn_history);
+                assert!(game.is_over());
+                assert_eq!(game.final_score(), finished.final_score());
+                assert_eq!(game.dump(), finished.dump());
+            }
+        }
+    }
+
+    #[test]
+    fn stacks_reflect_plays() {
+        let mut game = deal(&test_opts(2), &["r1 r2 y2 g3 b4", "y1 g1 g2 b1 w1"]);
+        assert_eq!(game.board.stacks(), COLORS.iter().map(|&color| (color, 0)).collect::<Vec<_>>());
+        game.process_choice(TurnChoice::Play(0));
+        assert_eq!(game.board.stacks(), vec![('r', 1), ('y', 0), ('g', 0), ('b', 0), ('w', 0)]);
+        // a misplay and a discard leave the stacks alone
+        game.process_choice(TurnChoice::Play(2));
+        game.process_choice(TurnChoice::Discard(1));
+        assert_eq!(game.board.stacks(), vec![('r', 1), ('y', 0), ('g', 0), ('b', 0), ('w', 0)]);
+        game.process_choice(TurnChoice::Play(0));
+        game.process_choice(TurnChoice::Play(0));
+        assert_eq!(game.board.stacks(), vec![('r', 2), ('y', 1), ('g', 0), ('b', 0), ('w', 0)]);
+    }
+
+    #[test]
+    fn last_discarded_is_most_recent() {
+        let mut game = deal(&test_opts(2), &["y3 r2 g3 b4 w5", "r1 r3 y1 g1 b1"]);
+        assert_eq!(game.board.discard.last_discarded(), None);
+        game.process_choice(TurnChoice::Discard(0));
+        assert_eq!(game.board.discard.last_discarded(), Some(&Card::new('y', 3)));
+        // misplays are discarded too
+        game.process_choice(TurnChoice::Play(1));
+        assert_eq!(game.board.discard.last_discarded(), Some(&Card::new('r', 3)));
+        // successful plays are not
+        game.process_choice(TurnChoice::Hint(Hint { player: 1, hinted: Hinted::Value(1) }));
+        game.process_choice(TurnChoice::Play(0));
+        assert_eq!(game.board.discard.last_discarded(), Some(&Card::new('r', 3)));
+    }
+
+    #[test]
+    fn value_distribution_of_full_deck() {
+        let deck = new_deck(&mut ChaChaRng::from_seed(&[0]));
+        let distribution = value_distribution(&deck);
+        assert_eq!(distribution[&1], 3 * NUM_COLORS as u32);
+        assert_eq!(distribution[&2], 2 * NUM_COLORS as u32);
+        assert_eq!(distribution[&3], 2 * NUM_COLORS as u32);
+        assert_eq!(distribution[&4], 2 * NUM_COLORS as u32);
+        assert_eq!(distribution[&5], NUM_COLORS as u32);
+        assert_eq!(distribution.values().sum::<u32>(), deck.len() as u32);
+    }
+
+    #[test]
+    fn choice_index_rejects_choices_without_index() {
+        let game = deal(&test_opts(3), &["r1 y2 g3 b4 w5", "r1 r2 r3 r4 r5", "y1 g1 b1 w1 y3"]);
+        let view = game.get_view(0);
+        assert_eq!(view.choice_index(&TurnChoice::Play(4)), Some(4));
+        assert_eq!(view.choice_index(&TurnChoice::Discard(4)), Some(9));
+        assert_eq!(view.choice_index(&hint(2, Hinted::Value(5))), Some(view.num_choice_indices() - 1));
+
+        assert_eq!(view.choice_index(&TurnChoice::Play(5)), None);
+        assert_eq!(view.choice_index(&TurnChoice::Discard(5)), None);
+        assert_eq!(view.choice_index(&hint(0, Hinted::Color('r'))), None);
+        assert_eq!(view.choice_index(&hint(3, Hinted::Color('r'))), None);
+        assert_eq!(view.choice_index(&hint(1, Hinted::Color('x'))), None);
+        assert_eq!(view.choice_index(&hint(1, Hinted::Value(0))), None);
+        assert_eq!(view.choice_index(&hint(1, Hinted::Value(6))), None);
+    }
 }
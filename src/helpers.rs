@@ -1,4 +1,4 @@
-use std::cmp::Eq;
+use std::cmp::{self, Eq};
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::ops::{Index,IndexMut};
@@ -6,11 +6,51 @@ use std::hash::Hash;
 use std::convert::From;
 use std::slice;
 
+use rand::{self, Rng, SeedableRng};
+
 use game::*;
 
+// how to choose between equally good slots
+#[derive(Clone)]
+pub enum TieBreak {
+    LowestSlot,
+    HighestSlot,
+    SeededRandom(rand::ChaChaRng),
+}
+impl TieBreak {
+    pub fn seeded_random(seed: u32) -> TieBreak {
+        TieBreak::SeededRandom(rand::ChaChaRng::from_seed(&[seed]))
+    }
+
+    // pick one of the tied slots
+    pub fn choose(&mut self, candidates: &[usize]) -> Option<usize> {
+        match *self {
+            TieBreak::LowestSlot => candidates.iter().cloned().min(),
+            TieBreak::HighestSlot => candidates.iter().cloned().max(),
+            TieBreak::SeededRandom(ref mut rng) => rng.choose(candidates).cloned(),
+        }
+    }
+}
+
+// lets boxed card infos be cloned, e.g. to fork a hand during search
+pub trait CardInfoClone {
+    fn clone_box(&self) -> Box<CardInfo>;
+}
+impl<T> CardInfoClone for T where T: 'static + CardInfo + Clone {
+    fn clone_box(&self) -> Box<CardInfo> {
+        Box::new(self.clone())
+    }
+}
+impl Clone for Box<CardInfo> {
+    fn clone(&self) -> Box<CardInfo> {
+        self.clone_box()
+    }
+}
+
 // trait representing information about a card
-pub trait CardInfo {
-    fn new() -> Self;
+// object safe, so that infos of different types can be boxed together
+pub trait CardInfo: fmt::Display + CardInfoClone {
+    fn new() -> Self where Self: Sized;
 
     // whether the card is possible
     fn is_possible(&self, card: &Card) -> bool;
@@ -30,18 +70,34 @@ pub trait CardInfo {
         v
     }
 
+    // possible values for each possible color
+    fn possibilities_by_color(&self) -> HashMap<Color, Vec<Value>> {
+        let mut by_color = HashMap::new();
+        for card in self.get_possibilities() {
+            by_color.entry(card.color).or_insert_with(Vec::new).push(card.value);
+        }
+        for values in by_color.values_mut() {
+            values.sort();
+        }
+        by_color
+    }
+
     // get probability weight for the card
+    // a weight of 0 means the card is impossible
     #[allow(unused_variables)]
     fn get_weight(&self, card: &Card) -> f32 {
         1 as f32
     }
 
+    // possible cards with their weights, leaving out zero-weight cards
     fn get_weighted_possibilities(&self) -> Vec<(Card, f32)> {
         self.get_possibilities().into_iter()
             .map(|card| {
                 let weight = self.get_weight(&card);
                 (card, weight)
-            }).collect::<Vec<_>>()
+            })
+            .filter(|&(_, weight)| weight > 0.)
+            .collect::<Vec<_>>()
     }
 
     fn total_weight(&self) -> f32 {
@@ -50,14 +106,12 @@ pub trait CardInfo {
             .fold(0.0, |a, b| a+b)
     }
 
-    fn weighted_score<T>(&self, score_fn: &Fn(&Card) -> T) -> f32
-        where f32: From<T>
-    {
+    fn weighted_score(&self, score_fn: &Fn(&Card) -> f32) -> f32 {
         let mut total_score = 0.;
         let mut total_weight = 0.;
         for card in self.get_possibilities() {
             let weight = self.get_weight(&card);
-            let score = f32::from(score_fn(&card));
+            let score = score_fn(&card);
             total_weight += weight;
             total_score += weight * score;
         }
@@ -79,6 +133,13 @@ pub trait CardInfo {
         self.probability_of_predicate(&|card| board.is_playable(card))
     }
 
+    // probability of being playable after teammates make the expected plays.
+    // this is only an estimate: it assumes exactly those plays happen, in order,
+    // and ignores anything discarded in the meantime
+    fn probability_is_playable_after(&self, board: &BoardState, expected_plays: &[Card]) -> f32 {
+        self.probability_is_playable(&board.after_plays(expected_plays))
+    }
+
     fn probability_is_dead(&self, board: &BoardState) -> f32 {
         self.probability_of_predicate(&|card| board.is_dead(card))
     }
@@ -87,6 +148,42 @@ pub trait CardInfo {
         self.probability_of_predicate(&|card| board.is_dispensable(card))
     }
 
+    // if every possibility is either playable or dead, the probability that it is playable,
+    // i.e. playing it can at worst cost a life. None if it might be a card we still need
+    fn playable_or_dead_probability(&self, board: &BoardState) -> Option<f32> {
+        let playable_or_dead = self.get_weighted_possibilities().iter().all(|&(ref card, _)| {
+            board.is_playable(card) || board.is_dead(card)
+        });
+        if playable_or_dead {
+            Some(self.probability_is_playable(board))
+        } else {
+            None
+        }
+    }
+
+    // expected benefit of keeping the card rather than discarding it:
+    // nothing for dead cards, otherwise the share of the unseen copies it represents,
+    // so that the last unseen copy of a needed card counts fully.
+    // unseen counts the copies seen elsewhere (played, discarded, or in other hands),
+    // so that unseen.remaining(card) is the number of copies that could still be this one
+    fn hold_value(&self, board: &BoardState, unseen: &CardCounts) -> f32 {
+        self.weighted_score(&|card| {
+            if board.is_dead(card) {
+                0.
+            } else {
+                1. / (cmp::max(unseen.remaining(card), 1) as f32)
+            }
+        })
+    }
+
+    fn probability_is_color(&self, color: Color) -> f32 {
+        self.probability_of_predicate(&|card| card.color == color)
+    }
+
+    fn probability_is_value(&self, value: Value) -> f32 {
+        self.probability_of_predicate(&|card| card.value == value)
+    }
+
     // mark a whole color as false
     fn mark_color_false(&mut self, color: Color);
     // mark a color as correct
@@ -122,6 +219,23 @@ pub trait CardInfo {
             self.mark_value_false(value);
         }
     }
+
+    // mark the result of a hint, given whether this card matched it
+    fn mark_hinted(&mut self, hinted: &Hinted, matched: bool) {
+        match hinted {
+            &Hinted::Color(color) => { self.mark_color(color, matched); }
+            &Hinted::Value(value) => { self.mark_value(value, matched); }
+        }
+    }
+}
+
+// information about a fresh card, after applying a sequence of hint results to it
+pub fn compose_hints<T>(hints: &[(Hinted, bool)]) -> T where T: CardInfo {
+    let mut info = T::new();
+    for &(ref hinted, matched) in hints {
+        info.mark_hinted(hinted, matched);
+    }
+    info
 }
 
 
@@ -135,9 +249,12 @@ pub trait Info<T> where T: Hash + Eq + Clone + Copy {
     fn get_possibility_set(&self) -> &HashSet<T>;
     fn get_mut_possibility_set(&mut self) -> &mut HashSet<T>;
 
-    // get what is now possible
+    // get what is now possible, in the order of get_all_possibilities
     fn get_possibilities(&self) -> Vec<T> {
-        self.get_possibility_set().iter().map(|t| t.clone()).collect::<Vec<T>>()
+        let possible = self.get_possibility_set();
+        Self::get_all_possibilities().into_iter()
+            .filter(|t| possible.contains(t))
+            .collect::<Vec<T>>()
     }
 
     fn is_possible(&self, value: T) -> bool {
@@ -164,7 +281,7 @@ pub trait Info<T> where T: Hash + Eq + Clone + Copy {
     }
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,Eq,PartialEq)]
 pub struct ColorInfo(HashSet<Color>);
 impl ColorInfo {
     pub fn new() -> ColorInfo { ColorInfo(ColorInfo::initialize()) }
@@ -175,7 +292,7 @@ impl Info<Color> for ColorInfo {
     fn get_mut_possibility_set(&mut self) -> &mut HashSet<Color> { &mut self.0 }
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,Eq,PartialEq)]
 pub struct ValueInfo(HashSet<Value>);
 impl ValueInfo {
     pub fn new() -> ValueInfo { ValueInfo(ValueInfo::initialize()) }
@@ -188,11 +305,46 @@ impl Info<Value> for ValueInfo {
 
 // represents information only of the form:
 // this color is/isn't possible, this value is/isn't possible
-#[derive(Debug,Clone)]
+#[derive(Clone,Eq,PartialEq)]
 pub struct SimpleCardInfo {
     pub color_info: ColorInfo,
     pub value_info: ValueInfo,
 }
+impl SimpleCardInfo {
+    // info that knows exactly which card it is
+    pub fn determined(card: &Card) -> SimpleCardInfo {
+        let mut info = SimpleCardInfo::new();
+        info.mark_color_true(card.color);
+        info.mark_value_true(card.value);
+        info
+    }
+
+    // whether a set of cards is a product of colors and values,
+    // i.e. whether some SimpleCardInfo has exactly these possibilities
+    pub fn can_represent_exactly(target: &HashSet<Card>) -> bool {
+        let colors = target.iter().map(|card| card.color).collect::<HashSet<_>>();
+        let values = target.iter().map(|card| card.value).collect::<HashSet<_>>();
+        colors.len() * values.len() == target.len()
+    }
+
+    // smallest info whose possibilities include all of the given info's.
+    // exact when those possibilities can be represented exactly, otherwise an over-approximation
+    pub fn covering<T>(info: &T) -> SimpleCardInfo where T: CardInfo {
+        let possibilities = info.get_possibilities();
+        let mut simple_info = SimpleCardInfo::new();
+        for &color in COLORS.iter() {
+            if !possibilities.iter().any(|card| card.color == color) {
+                simple_info.mark_color_false(color);
+            }
+        }
+        for &value in VALUES.iter() {
+            if !possibilities.iter().any(|card| card.value == value) {
+                simple_info.mark_value_false(value);
+            }
+        }
+        simple_info
+    }
+}
 impl CardInfo for SimpleCardInfo {
     fn new() -> SimpleCardInfo {
         SimpleCardInfo {
@@ -222,7 +374,19 @@ impl CardInfo for SimpleCardInfo {
     fn mark_value_false(&mut self, value: Value) {
         self.value_info.mark_false(value);
     }
+    // weight by copies in a full deck, like a fresh CardPossibilityTable
+    fn get_weight(&self, card: &Card) -> f32 {
+        if self.is_possible(card) {
+            get_count_for_value(card.value) as f32
+        } else {
+            0.
+        }
+    }
 }
+// width of the longest SimpleCardInfo notation, e.g. "rygbw 12345"
+pub const CARD_INFO_WIDTH: usize = NUM_COLORS + 1 + NUM_VALUES;
+
+// pads to the formatter's width, e.g. to CARD_INFO_WIDTH to align notations in columns
 impl fmt::Display for SimpleCardInfo {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut string = String::new();
@@ -242,20 +406,84 @@ impl fmt::Display for SimpleCardInfo {
         f.pad(&string)
     }
 }
+impl fmt::Debug for SimpleCardInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("SimpleCardInfo "));
+        f.debug_set().entries(self.get_possibilities()).finish()
+    }
+}
 
 // Can represent information of the form:
 // this card is/isn't possible
 // also, maintains integer weights for the cards
-#[derive(Clone,Debug,Eq,PartialEq)]
+#[derive(Clone,Eq,PartialEq)]
 pub struct CardPossibilityTable {
     possible: HashMap<Card, u32>,
 }
 impl CardPossibilityTable {
+    // table of the cards in the deck, weighted by their copies.
+    // reserves room for every distinct card up front, to avoid rehashing
+    pub fn with_capacity(deck: &Cards) -> CardPossibilityTable {
+        let distinct = deck.iter().collect::<HashSet<_>>().len();
+        let mut possible = HashMap::with_capacity(distinct);
+        for card in deck {
+            *possible.entry(card.clone()).or_insert(0) += 1;
+        }
+        CardPossibilityTable {
+            possible: possible,
+        }
+    }
+
+    // table in which only the given card is possible
+    pub fn determined(card: &Card) -> CardPossibilityTable {
+        let mut possible = HashMap::new();
+        possible.insert(card.clone(), 1);
+        CardPossibilityTable {
+            possible: possible,
+        }
+    }
+
+    // table with the possibilities of a SimpleCardInfo, weighted by what remains given the counts.
+    // together with SimpleCardInfo::covering, this round-trips tables that are rectangular
+    // and weighted by the same counts; anything else loses information
+    pub fn from_simple_info(info: &SimpleCardInfo, counts: &CardCounts) -> CardPossibilityTable {
+        let mut table = CardPossibilityTable::from(counts);
+        for &color in COLORS.iter() {
+            if !info.color_info.is_possible(color) {
+                table.mark_color_false(color);
+            }
+        }
+        for &value in VALUES.iter() {
+            if !info.value_info.is_possible(value) {
+                table.mark_value_false(value);
+            }
+        }
+        table
+    }
+
     // mark a possible card as false
     pub fn mark_false(&mut self, card: &Card) {
         self.possible.remove(card);
     }
 
+    // mark every card that isn't currently playable as false
+    pub fn retain_playable(&mut self, board: &BoardState) {
+        self.possible.retain(|card, _| board.is_playable(card));
+    }
+
+    // a play clue touched this card: it matches the hint, and is playable
+    pub fn apply_play_clue(&mut self, hinted: &Hinted, board: &BoardState) {
+        self.mark_hinted(hinted, true);
+        self.retain_playable(board);
+    }
+
+    // if every copy of the card is accounted for elsewhere, it can't be this one
+    pub fn eliminate_exhausted(&mut self, card: &Card, copies_elsewhere: u32, deck_copies: u32) {
+        if copies_elsewhere >= deck_copies {
+            self.mark_false(card);
+        }
+    }
+
     // a bit more efficient
     // pub fn borrow_possibilities<'a>(&'a self) -> Vec<&'a Card> {
     //     self.possible.keys().collect::<Vec<_>>()
@@ -280,17 +508,50 @@ impl CardPossibilityTable {
         }
     }
 
+    // cards possible in either this or the other info, with weights summed
+    pub fn union<T>(&self, other: &T) -> CardPossibilityTable where T: CardInfo {
+        let mut possible = self.possible.clone();
+        for card in other.get_possibilities() {
+            let weight = other.get_weight(&card) as u32;
+            if weight > 0 {
+                *possible.entry(card).or_insert(0) += weight;
+            }
+        }
+        CardPossibilityTable {
+            possible: possible,
+        }
+    }
+
+    // possible cards with their weights, sorted by card
+    pub fn weights_vec(&self) -> Vec<(Card, u32)> {
+        let mut weights = self.possible.iter().map(|(card, &weight)| {
+            (card.clone(), weight)
+        }).collect::<Vec<_>>();
+        weights.sort();
+        weights
+    }
+
+    // the weight of a card is the number of its copies that are not yet accounted for,
+    // so it's how many copies could still be this card (0 if impossible)
+    pub fn expected_remaining(&self, card: &Card) -> f32 {
+        self.get_weight(card)
+    }
+
+    // number of possible cards, without sorting them
+    pub fn count_possibilities(&self) -> usize {
+        self.possible.len()
+    }
+
     pub fn get_card(&self) -> Option<Card> {
-        let possibilities = self.get_possibilities();
-        if possibilities.len() == 1 {
-            Some(possibilities[0].clone())
+        if self.count_possibilities() == 1 {
+            self.possible.keys().next().cloned()
         } else {
             None
         }
     }
 
     pub fn is_determined(&self) -> bool {
-        self.get_possibilities().len() == 1
+        self.count_possibilities() == 1
     }
 
     pub fn color_determined(&self) -> bool {
@@ -308,16 +569,17 @@ impl CardPossibilityTable {
     }
 
     pub fn can_be_color(&self, color: Color) -> bool {
-        self.get_possibilities().into_iter().any(|card| card.color == color)
+        self.possible.keys().any(|card| card.color == color)
     }
 
     pub fn can_be_value(&self, value: Value) -> bool {
-        self.get_possibilities().into_iter().any(|card| card.value == value)
+        self.possible.keys().any(|card| card.value == value)
     }
 }
 impl <'a> From<&'a CardCounts> for CardPossibilityTable {
     fn from(counts: &'a CardCounts) -> CardPossibilityTable {
-        let mut possible = HashMap::new();
+        // reserve room for every distinct card up front, to avoid rehashing
+        let mut possible = HashMap::with_capacity(NUM_COLORS * NUM_VALUES);
         for &color in COLORS.iter() {
             for &value in VALUES.iter() {
                 let card = Card::new(color, value);
@@ -334,7 +596,7 @@ impl <'a> From<&'a CardCounts> for CardPossibilityTable {
 }
 impl CardInfo for CardPossibilityTable {
     fn new() -> CardPossibilityTable {
-        Self::from(&CardCounts::new())
+        Self::with_capacity(&full_deck())
     }
 
     fn is_possible(&self, card: &Card) -> bool {
@@ -368,6 +630,12 @@ impl fmt::Display for CardPossibilityTable {
         Ok(())
     }
 }
+impl fmt::Debug for CardPossibilityTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        try!(f.write_str("CardPossibilityTable "));
+        f.debug_map().entries(self.weights_vec()).finish()
+    }
+}
 
 #[derive(Clone,Eq,PartialEq)]
 pub struct HandInfo<T> where T: CardInfo {
@@ -383,26 +651,94 @@ impl <T> HandInfo<T> where T: CardInfo {
 
     // update for hint to me
     pub fn update_for_hint(&mut self, hinted: &Hinted, matches: &Vec<bool>) {
-        match hinted {
-            &Hinted::Color(color) => {
-                for (card_info, &matched) in self.hand_info.iter_mut().zip(matches.iter()) {
-                    card_info.mark_color(color, matched);
-                }
-            }
-            &Hinted::Value(value) => {
-                for (card_info, &matched) in self.hand_info.iter_mut().zip(matches.iter()) {
-                    card_info.mark_value(value, matched);
-                }
-            }
+        for (card_info, &matched) in self.hand_info.iter_mut().zip(matches.iter()) {
+            card_info.mark_hinted(hinted, matched);
         }
     }
 
+    // whether update_for_hint would rule anything out, i.e. whether the hint tells us anything.
+    // this covers every slot, not just the touched ones: re-cluing a fully determined card
+    // still counts as informative if an untouched slot learns that it isn't the hinted color or value
+    pub fn would_learn_from_hint(&self, hinted: &Hinted, matches: &Vec<bool>) -> bool {
+        self.hand_info.iter().zip(matches.iter()).any(|(card_info, &matched)| {
+            card_info.get_possibilities().iter().any(|card| {
+                let touched = match hinted {
+                    &Hinted::Color(color) => card.color == color,
+                    &Hinted::Value(value) => card.value == value,
+                };
+                touched != matched
+            })
+        })
+    }
+
+    // probability that each slot is playable
+    pub fn probabilities_playable(&self, board: &BoardState) -> Vec<f32> {
+        self.hand_info.iter().map(|card_info| card_info.probability_is_playable(board)).collect()
+    }
+
+    // slots whose card is fully determined, along with the card
+    pub fn known_cards(&self) -> Vec<(usize, Card)> {
+        self.hand_info.iter().enumerate().filter_map(|(i, card_info)| {
+            let mut possibilities = card_info.get_possibilities();
+            if possibilities.len() == 1 {
+                Some((i, possibilities.remove(0)))
+            } else {
+                None
+            }
+        }).collect()
+    }
+
     pub fn remove(&mut self, index: usize) -> T { self.hand_info.remove(index) }
     pub fn push(&mut self, card_info: T)        { self.hand_info.push(card_info) }
     pub fn iter_mut(&mut self) -> slice::IterMut<T> { self.hand_info.iter_mut() }
     pub fn iter(&self) -> slice::Iter<T>        { self.hand_info.iter() }
     pub fn len(&self) -> usize                  { self.hand_info.len() }
 }
+impl <T> HandInfo<T> where T: CardInfo + PartialEq {
+    // drop plays and discards of slots whose info equals that of an earlier slot,
+    // since from the player's perspective those choices are interchangeable
+    pub fn dedupe_equivalent(&self, choices: Vec<TurnChoice>) -> Vec<TurnChoice> {
+        choices.into_iter().filter(|choice| {
+            let index = match choice {
+                &TurnChoice::Play(index) | &TurnChoice::Discard(index) => index,
+                &TurnChoice::Hint(_) => { return true; }
+            };
+            !self.hand_info[..index].contains(&self.hand_info[index])
+        }).collect()
+    }
+}
+impl HandInfo<CardPossibilityTable> {
+    // info for the remaining slots, assuming the card at index turns out to be `card`
+    // and is played on the given board. approximate, for lookahead. eliminations applied:
+    //  - that copy is accounted for, so it becomes less likely for each other slot
+    //  - if the card is playable or already dead, it is dead once played. under good touch,
+    //    the remaining slots hold no trash, so they can't be that card at all
+    pub fn after_removing(&self, index: usize, card: &Card, board: &BoardState) -> Self {
+        let dead_after_play = board.is_playable(card) || board.is_dead(card);
+        let mut hand_info = self.clone();
+        hand_info.remove(index);
+        for card_table in hand_info.iter_mut() {
+            if dead_after_play {
+                card_table.mark_false(card);
+            } else {
+                card_table.decrement_weight_if_possible(card);
+            }
+        }
+        hand_info
+    }
+}
+// the notation of each slot, padded to a fixed width so that hands line up
+impl fmt::Display for HandInfo<SimpleCardInfo> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, card_info) in self.hand_info.iter().enumerate() {
+            if i > 0 {
+                try!(f.write_str(" | "));
+            }
+            try!(f.write_str(&format!("{:<width$}", card_info, width = CARD_INFO_WIDTH)));
+        }
+        Ok(())
+    }
+}
 impl <T> Index<usize> for HandInfo<T> where T: CardInfo {
     type Output = T;
     fn index(&self, index: usize) -> &T {
@@ -414,3 +750,599 @@ impl <T> IndexMut<usize> for HandInfo<T> where T: CardInfo {
         &mut self.hand_info[index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use super::*;
+    use game::tests::{deal, test_opts};
+
+    fn possible_cards<T>(hints: &[(Hinted, bool)]) -> Vec<Card> where T: CardInfo {
+        let mut cards = compose_hints::<T>(hints).get_possibilities();
+        cards.sort();
+        cards
+    }
+
+    fn cards(colors: &str, values: &[Value]) -> Vec<Card> {
+        let mut cards = colors.chars().flat_map(|color| {
+            values.iter().map(move |&value| Card::new(color, value))
+        }).collect::<Vec<_>>();
+        cards.sort();
+        cards
+    }
+
+    #[test]
+    fn compose_hints_takes_product_of_constraints() {
+        let cases = vec![
+            (vec![], cards("rygbw", &VALUES)),
+            // "it's a 3, and it's not red"
+            (vec![(Hinted::Value(3), true), (Hinted::Color('r'), false)], cards("ygbw", &[3])),
+            (vec![(Hinted::Color('r'), true), (Hinted::Value(1), true)], cards("r", &[1])),
+            (vec![(Hinted::Color('r'), false), (Hinted::Value(3), false)], cards("ygbw", &[1, 2, 4, 5])),
+            (vec![(Hinted::Color('b'), true), (Hinted::Value(5), false), (Hinted::Value(1), false)],
+             cards("b", &[2, 3, 4])),
+            (vec![(Hinted::Value(2), true), (Hinted::Value(2), true)], cards("rygbw", &[2])),
+            (vec![(Hinted::Color('g'), true), (Hinted::Color('g'), false)], vec![]),
+        ];
+        for (hints, expected) in cases {
+            let mut reversed = hints.clone();
+            reversed.reverse();
+            assert_eq!(possible_cards::<SimpleCardInfo>(&hints), expected, "{:?}", hints);
+            assert_eq!(possible_cards::<SimpleCardInfo>(&reversed), expected, "{:?}", reversed);
+            assert_eq!(possible_cards::<CardPossibilityTable>(&hints), expected, "{:?}", hints);
+            assert_eq!(possible_cards::<CardPossibilityTable>(&reversed), expected, "{:?}", reversed);
+        }
+    }
+
+    #[test]
+    fn union_of_disjoint_tables_has_both_cards() {
+        let red_one = compose_hints::<CardPossibilityTable>(&[(Hinted::Color('r'), true), (Hinted::Value(1), true)]);
+        let yellow_two = compose_hints::<CardPossibilityTable>(&[(Hinted::Color('y'), true), (Hinted::Value(2), true)]);
+        let expected = vec![(Card::new('r', 1), 3), (Card::new('y', 2), 2)];
+        assert_eq!(red_one.union(&yellow_two).weights_vec(), expected);
+        assert_eq!(yellow_two.union(&red_one).weights_vec(), expected);
+        // overlapping possibilities have their weights summed
+        assert_eq!(red_one.union(&red_one).weights_vec(), vec![(Card::new('r', 1), 6)]);
+    }
+
+    #[test]
+    fn play_clue_on_single_started_color_identifies_card() {
+        let opts = test_opts(2);
+        let mut game = deal(&opts, &["r1 y2 g3 b4 w5", "r2 y1 g1 b1 w1"]);
+        game.process_choice(TurnChoice::Play(0));
+
+        let mut card_table = CardPossibilityTable::new();
+        card_table.apply_play_clue(&Hinted::Value(2), &game.board);
+        assert_eq!(card_table.get_possibilities(), vec![Card::new('r', 2)]);
+
+        // with two colors at 1, the clue leaves both 2s
+        game.process_choice(TurnChoice::Play(1));
+        let mut card_table = CardPossibilityTable::new();
+        card_table.apply_play_clue(&Hinted::Value(2), &game.board);
+        let mut possibilities = card_table.get_possibilities();
+        possibilities.sort();
+        assert_eq!(possibilities, vec![Card::new('r', 2), Card::new('y', 2)]);
+    }
+
+    #[test]
+    fn playing_red_one_removes_it_from_siblings() {
+        let opts = test_opts(2);
+        let game = deal(&opts, &["y1 y2 g3 b4 w5", "r2 r3 g1 b1 w1"]);
+        let mut hand_info = HandInfo::<CardPossibilityTable>::new(3);
+        hand_info.update_for_hint(&Hinted::Value(1), &vec![true, true, false]);
+        let red_one = Card::new('r', 1);
+
+        let after = hand_info.after_removing(0, &red_one, &game.board);
+        assert_eq!(after.len(), 2);
+        assert!(!after[0].is_possible(&red_one));
+        assert!(after[0].is_possible(&Card::new('y', 1)));
+        // the untouched slot can't be a 1 either way, and loses nothing else
+        assert_eq!(after[1], hand_info[2]);
+
+        // a card that can't be played yet only loses the copy
+        let red_two = Card::new('r', 2);
+        let after = HandInfo::<CardPossibilityTable>::new(2).after_removing(0, &red_two, &game.board);
+        assert_eq!(after[0].get_weight(&red_two), 1.0);
+    }
+
+    #[test]
+    fn hand_info_rows_have_the_same_length() {
+        let mut hands = vec![HandInfo::<SimpleCardInfo>::new(4)];
+        let mut hand_info = HandInfo::<SimpleCardInfo>::new(4);
+        hand_info.update_for_hint(&Hinted::Color('r'), &vec![true, false, false, true]);
+        hands.push(hand_info.clone());
+        hand_info.update_for_hint(&Hinted::Value(1), &vec![false, true, false, true]);
+        hands.push(hand_info.clone());
+        hand_info.update_for_hint(&Hinted::Value(5), &vec![false, false, true, false]);
+        hands.push(hand_info);
+
+        let rows = hands.iter().map(|hand_info| format!("{}", hand_info)).collect::<Vec<_>>();
+        assert_eq!(rows[0], "rygbw 12345 | rygbw 12345 | rygbw 12345 | rygbw 12345");
+        assert_eq!(rows[3], "r 234       | ygbw 1      | ygbw 5      | r 1        ");
+        assert!(rows.iter().all(|row| row.len() == rows[0].len()), "{:?}", rows);
+    }
+
+    #[test]
+    fn tie_breaks_pick_opposite_ends() {
+        let candidates = vec![3, 1, 4, 2];
+        assert_eq!(TieBreak::LowestSlot.choose(&candidates), Some(1));
+        assert_eq!(TieBreak::HighestSlot.choose(&candidates), Some(4));
+        assert_eq!(TieBreak::LowestSlot.choose(&[]), None);
+        assert_eq!(TieBreak::HighestSlot.choose(&[]), None);
+    }
+
+    #[test]
+    fn seeded_random_tie_break_is_reproducible() {
+        let candidates = (0..5).collect::<Vec<usize>>();
+        let picks = |seed| {
+            let mut tie_break = TieBreak::seeded_random(seed);
+            (0..20).map(|_| tie_break.choose(&candidates).unwrap()).collect::<Vec<_>>()
+        };
+        assert_eq!(picks(0), picks(0));
+        assert_eq!(picks(7), picks(7));
+        assert!(picks(0) != picks(7));
+        // clones continue from the same state
+        let mut tie_break = TieBreak::seeded_random(3);
+        tie_break.choose(&candidates);
+        let mut clone = tie_break.clone();
+        assert_eq!(tie_break.choose(&candidates), clone.choose(&candidates));
+    }
+
+    // checks the Info contract for the given info and, recursively, for everything reachable
+    // from it by up to `depth` more calls to mark_true/mark_false:
+    //  - the possibilities stay a subset of all possibilities, listed in canonical order
+    //  - mark_true(x) leaves exactly x
+    //  - mark_false(x) removes exactly x, and doing it again changes nothing
+    //  - mark dispatches to mark_true/mark_false
+    fn check_info_contract<T, I>(info: &I, depth: usize)
+        where T: Hash + Eq + Clone + Copy + Debug, I: Info<T> + Clone
+    {
+        let all = I::get_all_possibilities();
+        assert!(info.get_possibility_set().iter().all(|t| all.contains(t)));
+        let expected = all.iter().cloned().filter(|&t| info.is_possible(t)).collect::<Vec<_>>();
+        assert_eq!(info.get_possibilities(), expected);
+        if depth == 0 {
+            return;
+        }
+
+        for &t in all.iter() {
+            let mut marked_true = info.clone();
+            marked_true.mark_true(t);
+            assert_eq!(marked_true.get_possibilities(), vec![t]);
+
+            let mut marked_false = info.clone();
+            marked_false.mark_false(t);
+            for &other in all.iter() {
+                let expected = other != t && info.is_possible(other);
+                assert_eq!(marked_false.is_possible(other), expected);
+            }
+            let mut marked_false_twice = marked_false.clone();
+            marked_false_twice.mark_false(t);
+            assert_eq!(marked_false_twice.get_possibility_set(), marked_false.get_possibility_set());
+
+            let mut marked = info.clone();
+            marked.mark(t, true);
+            assert_eq!(marked.get_possibility_set(), marked_true.get_possibility_set());
+            let mut marked = info.clone();
+            marked.mark(t, false);
+            assert_eq!(marked.get_possibility_set(), marked_false.get_possibility_set());
+
+            check_info_contract(&marked_true, depth - 1);
+            check_info_contract(&marked_false, depth - 1);
+        }
+    }
+
+    #[test]
+    fn color_info_satisfies_info_contract() {
+        check_info_contract(&ColorInfo::new(), 4);
+    }
+
+    #[test]
+    fn value_info_satisfies_info_contract() {
+        check_info_contract(&ValueInfo::new(), 4);
+    }
+
+    #[test]
+    fn known_card_is_playable_after_expected_plays() {
+        let board = deal(&test_opts(2), &["r1 y2 g3 b4 w5", "r1 r2 r3 r4 r5"]).board;
+        let red_one = Card::new('r', 1);
+        let red_two = Card::new('r', 2);
+        let infos: Vec<Box<CardInfo>> = vec![
+            Box::new(SimpleCardInfo::determined(&red_two)),
+            Box::new(CardPossibilityTable::determined(&red_two)),
+        ];
+        for info in &infos {
+            assert_eq!(info.probability_is_playable(&board), 0.0);
+            assert_eq!(info.probability_is_playable_after(&board, &[red_one.clone()]), 1.0);
+            // plays that can't happen yet are skipped
+            assert_eq!(info.probability_is_playable_after(&board, &[red_two.clone(), red_one.clone()]), 1.0);
+            assert_eq!(info.probability_is_playable_after(&board, &[Card::new('y', 1)]), 0.0);
+        }
+        assert_eq!(board.score(), 0);
+    }
+
+    #[test]
+    fn card_infos_of_different_types_box_together() {
+        let red_one = Card::new('r', 1);
+        let infos: Vec<Box<CardInfo>> = vec![
+            Box::new(SimpleCardInfo::determined(&red_one)),
+            Box::new(CardPossibilityTable::determined(&red_one)),
+        ];
+        let formatted = infos.iter().map(|info| format!("{}", info)).collect::<Vec<_>>();
+        assert_eq!(formatted, vec!["r 1", "1 r1, "]);
+        for info in &infos {
+            assert_eq!(info.get_possibilities(), vec![red_one.clone()]);
+        }
+    }
+
+    #[test]
+    fn cloned_boxed_info_is_independent() {
+        let original: Box<CardInfo> = Box::new(CardPossibilityTable::new());
+        let mut clone = original.clone();
+        assert_eq!(clone.get_weighted_possibilities(), original.get_weighted_possibilities());
+
+        clone.mark_color_false('r');
+        assert!(!clone.is_possible(&Card::new('r', 1)));
+        assert!(original.is_possible(&Card::new('r', 1)));
+        assert_eq!(original.get_weighted_possibilities(), CardPossibilityTable::new().get_weighted_possibilities());
+        assert_eq!(original.get_possibilities().len(), 25);
+        assert_eq!(clone.get_possibilities().len(), 20);
+    }
+
+    #[test]
+    fn rectangular_table_round_trips_through_simple_info() {
+        let counts = CardCounts::new();
+        let mut table = CardPossibilityTable::from(&counts);
+        table.mark_color_false('r');
+        table.mark_value_false(5);
+        let simple_info = SimpleCardInfo::covering(&table);
+        assert_eq!(format!("{}", simple_info), "ygbw 1234");
+        assert_eq!(CardPossibilityTable::from_simple_info(&simple_info, &counts), table);
+    }
+
+    #[test]
+    fn non_rectangular_table_comes_back_as_covering() {
+        let counts = CardCounts::new();
+        let mut table = CardPossibilityTable::from(&counts);
+        table.mark_color_false('r');
+        table.mark_false(&Card::new('y', 1));
+        let simple_info = SimpleCardInfo::covering(&table);
+        assert_eq!(format!("{}", simple_info), "ygbw 12345");
+
+        let mut covering = CardPossibilityTable::from(&counts);
+        covering.mark_color_false('r');
+        let round_tripped = CardPossibilityTable::from_simple_info(&simple_info, &counts);
+        assert_eq!(round_tripped, covering);
+        assert!(round_tripped != table);
+    }
+
+    #[test]
+    fn simple_info_weights_match_fresh_table() {
+        let simple_info = SimpleCardInfo::new();
+        let table = CardPossibilityTable::new();
+        for &color in COLORS.iter() {
+            for &value in VALUES.iter() {
+                let card = Card::new(color, value);
+                assert_eq!(simple_info.get_weight(&card), table.get_weight(&card), "{}", card);
+            }
+        }
+        assert_eq!(simple_info.total_weight(), table.total_weight());
+
+        let mut simple_info = SimpleCardInfo::new();
+        simple_info.mark_color_false('r');
+        assert_eq!(simple_info.get_weight(&Card::new('r', 1)), 0.0);
+        assert_eq!(simple_info.get_weight(&Card::new('y', 1)), 3.0);
+    }
+
+    // a possible card can still have zero weight, e.g. in infos that track weights separately
+    #[derive(Clone)]
+    struct ZeroWeightInfo(SimpleCardInfo, Card);
+    impl fmt::Display for ZeroWeightInfo {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+    impl CardInfo for ZeroWeightInfo {
+        fn new() -> ZeroWeightInfo { ZeroWeightInfo(SimpleCardInfo::new(), Card::new('r', 1)) }
+        fn is_possible(&self, card: &Card) -> bool { self.0.is_possible(card) }
+        fn mark_color_false(&mut self, color: Color) { self.0.mark_color_false(color); }
+        fn mark_value_false(&mut self, value: Value) { self.0.mark_value_false(value); }
+        fn get_weight(&self, card: &Card) -> f32 {
+            if *card == self.1 { 0. } else { self.0.get_weight(card) }
+        }
+    }
+
+    #[test]
+    fn weighted_possibilities_leave_out_zero_weights() {
+        let info = compose_hints::<ZeroWeightInfo>(&[(Hinted::Value(1), true), (Hinted::Color('b'), false)]);
+        assert!(info.is_possible(&Card::new('r', 1)));
+        assert_eq!(info.get_possibilities().len(), 4);
+        let weighted = info.get_weighted_possibilities();
+        assert_eq!(weighted, vec![(Card::new('y', 1), 3.), (Card::new('g', 1), 3.), (Card::new('w', 1), 3.)]);
+    }
+
+    #[test]
+    fn dedupe_equivalent_collapses_equal_slots() {
+        let mut hand_info = HandInfo::<SimpleCardInfo>::new(3);
+        // slots 0 and 2 are both known to be a 1, slot 1 is known not to be
+        hand_info.update_for_hint(&Hinted::Value(1), &vec![true, false, true]);
+        let hints = vec![
+            TurnChoice::Hint(Hint { player: 1, hinted: Hinted::Color('r') }),
+            TurnChoice::Hint(Hint { player: 1, hinted: Hinted::Color('r') }),
+        ];
+        let mut choices = (0..3).map(TurnChoice::Play).chain((0..3).map(TurnChoice::Discard)).collect::<Vec<_>>();
+        choices.extend(hints.clone());
+
+        let mut expected = vec![
+            TurnChoice::Play(0), TurnChoice::Play(1),
+            TurnChoice::Discard(0), TurnChoice::Discard(1),
+        ];
+        expected.extend(hints);
+        assert_eq!(hand_info.dedupe_equivalent(choices), expected);
+    }
+
+    #[test]
+    fn playable_or_dead_probability_needs_every_card_safe() {
+        let mut game = deal(&test_opts(2), &["r1 y2 g3 b4 w5", "b1 r3 r4 y4 w4"]);
+        game.process_choice(TurnChoice::Play(0));
+        game.process_choice(TurnChoice::Play(0));
+        let board = &game.board;
+
+        let table = [Card::new('r', 1), Card::new('b', 1), Card::new('b', 1)].iter()
+            .fold(CardPossibilityTable::determined(&Card::new('r', 2)), |table, card| {
+                table.union(&CardPossibilityTable::determined(card))
+            });
+        assert_eq!(table.playable_or_dead_probability(board), Some(0.25));
+
+        let with_needed = table.union(&CardPossibilityTable::determined(&Card::new('r', 3)));
+        assert_eq!(with_needed.playable_or_dead_probability(board), None);
+    }
+
+    #[test]
+    fn reclue_of_determined_card_is_not_informative() {
+        let red = Hinted::Color('r');
+        let mut hand_info = HandInfo::<SimpleCardInfo>::new(3);
+        hand_info.update_for_hint(&red, &vec![true, false, false]);
+        hand_info.update_for_hint(&Hinted::Value(1), &vec![true, false, false]);
+        // slot 0 is known to be r1, and the other slots already exclude red
+        assert!(!hand_info.would_learn_from_hint(&red, &vec![true, false, false]));
+        assert!(!hand_info.would_learn_from_hint(&Hinted::Value(1), &vec![true, false, false]));
+        // a hint touching an uncertain slot narrows it
+        assert!(hand_info.would_learn_from_hint(&Hinted::Value(2), &vec![false, true, false]));
+
+        // with the same card determined, but an untouched slot that could still be red,
+        // the re-clue tells that slot it isn't
+        let mut hand_info = HandInfo::<SimpleCardInfo>::new(2);
+        hand_info[0] = SimpleCardInfo::determined(&Card::new('r', 1));
+        assert!(hand_info.would_learn_from_hint(&red, &vec![true, false]));
+    }
+
+    #[test]
+    fn likely_critical_card_is_worth_holding() {
+        let mut game = deal(&test_opts(2), &["y1 y2 g3 b4 w5", "r1 r2 r3 r4 r5"]);
+        game.process_choice(TurnChoice::Play(0));
+        let board = &game.board;
+        // the holder has seen the played y1, and the other player's hand
+        let mut unseen = CardCounts::new();
+        unseen.increment(&Card::new('y', 1));
+        for card in &game.hands[&1] {
+            unseen.increment(card);
+        }
+
+        let mixed = |likely: &Card, unlikely: &Card| {
+            let mut table = CardPossibilityTable::determined(likely);
+            for _ in 0..2 {
+                table = table.union(&CardPossibilityTable::determined(likely));
+            }
+            table.union(&CardPossibilityTable::determined(unlikely))
+        };
+        let white_five = Card::new('w', 5);
+        let yellow_one = Card::new('y', 1);
+        let likely_critical = mixed(&white_five, &yellow_one);
+        let likely_trash = mixed(&yellow_one, &white_five);
+        assert_eq!(likely_critical.hold_value(board, &unseen), 0.75);
+        assert_eq!(likely_trash.hold_value(board, &unseen), 0.25);
+
+        // a needed card counts less when another unseen copy could turn up
+        let green_two = CardPossibilityTable::determined(&Card::new('g', 2));
+        assert_eq!(green_two.hold_value(board, &unseen), 0.5);
+        // while one whose other copy is in a teammate's hand counts fully
+        let red_two = CardPossibilityTable::determined(&Card::new('r', 2));
+        assert_eq!(red_two.hold_value(board, &unseen), 1.0);
+    }
+
+    #[test]
+    fn table_queries_agree_with_possibilities() {
+        let marks = vec![
+            (Hinted::Color('r'), false),
+            (Hinted::Value(5), false),
+            (Hinted::Color('y'), false),
+            (Hinted::Value(1), true),
+            (Hinted::Color('g'), true),
+        ];
+        let mut table = CardPossibilityTable::new();
+        for &(ref hinted, matched) in &marks {
+            table.mark_hinted(hinted, matched);
+            let possibilities = table.get_possibilities();
+            assert_eq!(table.count_possibilities(), possibilities.len());
+            assert_eq!(table.is_determined(), possibilities.len() == 1);
+            for &color in COLORS.iter() {
+                assert_eq!(table.can_be_color(color), possibilities.iter().any(|card| card.color == color));
+            }
+            for &value in VALUES.iter() {
+                assert_eq!(table.can_be_value(value), possibilities.iter().any(|card| card.value == value));
+            }
+        }
+        assert_eq!(table.get_card(), Some(Card::new('g', 1)));
+        table.mark_false(&Card::new('g', 1));
+        assert_eq!(table.count_possibilities(), 0);
+        assert_eq!(table.get_card(), None);
+    }
+
+    #[test]
+    fn weights_vec_is_sorted_and_matches_weighted_possibilities() {
+        let mut table = CardPossibilityTable::new();
+        table.mark_value_false(3);
+        table.decrement_weight(&Card::new('b', 1));
+        table.decrement_weight(&Card::new('w', 4));
+        let weights = table.weights_vec();
+        let mut sorted = weights.clone();
+        sorted.sort();
+        assert_eq!(weights, sorted);
+
+        let weighted = table.get_weighted_possibilities().into_iter().map(|(card, weight)| {
+            (card, weight as u32)
+        }).collect::<Vec<_>>();
+        assert_eq!(weights.len(), 20);
+        assert_eq!(weights, weighted);
+        assert!(weights.contains(&(Card::new('b', 1), 2)));
+        assert!(weights.contains(&(Card::new('w', 4), 1)));
+    }
+
+    #[test]
+    fn debug_shows_possible_cards() {
+        let hints = [(Hinted::Color('r'), true), (Hinted::Value(1), true)];
+        let simple_info = compose_hints::<SimpleCardInfo>(&hints);
+        let table = compose_hints::<CardPossibilityTable>(&hints);
+        assert_eq!(format!("{:?}", simple_info), "SimpleCardInfo {r1}");
+        assert_eq!(format!("{:?}", table), "CardPossibilityTable {r1: 3}");
+
+        let simple_info = compose_hints::<SimpleCardInfo>(&[(Hinted::Value(5), true), (Hinted::Color('b'), false)]);
+        let debug = format!("{:?}", simple_info);
+        assert!(debug.contains("r5") && debug.contains("w5") && !debug.contains("b5"), "{}", debug);
+    }
+
+    #[test]
+    fn known_cards_lists_only_determined_slots() {
+        let mut hand_info = HandInfo::<CardPossibilityTable>::new(4);
+        hand_info.update_for_hint(&Hinted::Color('y'), &vec![false, true, true, false]);
+        hand_info.update_for_hint(&Hinted::Value(4), &vec![false, true, false, true]);
+        assert_eq!(hand_info.known_cards(), vec![(1, Card::new('y', 4))]);
+
+        let hand_info = HandInfo::<SimpleCardInfo>::new(3);
+        assert_eq!(hand_info.known_cards(), vec![]);
+    }
+
+    #[test]
+    fn determined_infos_know_the_card() {
+        for &color in COLORS.iter() {
+            for &value in VALUES.iter() {
+                let card = Card::new(color, value);
+                let table = CardPossibilityTable::determined(&card);
+                assert!(table.is_determined());
+                assert_eq!(table.get_card(), Some(card.clone()));
+                assert_eq!(table.get_possibilities(), vec![card.clone()]);
+                assert_eq!(SimpleCardInfo::determined(&card).get_possibilities(), vec![card]);
+            }
+        }
+    }
+
+    #[test]
+    fn seeing_every_copy_eliminates_the_card() {
+        let green_two = Card::new('g', 2);
+        let copies = get_count_for_value(2);
+
+        let mut table = CardPossibilityTable::new();
+        table.eliminate_exhausted(&green_two, 1, copies);
+        assert!(table.is_possible(&green_two));
+
+        table.eliminate_exhausted(&green_two, 2, copies);
+        assert!(!table.is_possible(&green_two));
+        assert_eq!(table.count_possibilities(), 24);
+    }
+
+    #[test]
+    fn expected_remaining_follows_decrements() {
+        let red_one = Card::new('r', 1);
+        let mut table = CardPossibilityTable::new();
+        assert_eq!(table.expected_remaining(&red_one), 3.0);
+        table.decrement_weight(&red_one);
+        assert_eq!(table.expected_remaining(&red_one), 2.0);
+        table.decrement_weight(&red_one);
+        table.decrement_weight(&red_one);
+        assert_eq!(table.expected_remaining(&red_one), 0.0);
+        assert!(!table.is_possible(&red_one));
+        assert_eq!(table.expected_remaining(&Card::new('r', 5)), 1.0);
+    }
+
+    #[test]
+    fn possibilities_by_color_after_value_hint() {
+        let hints = [(Hinted::Value(3), true)];
+        let infos: Vec<Box<CardInfo>> = vec![
+            Box::new(compose_hints::<SimpleCardInfo>(&hints)),
+            Box::new(compose_hints::<CardPossibilityTable>(&hints)),
+        ];
+        for info in &infos {
+            let by_color = info.possibilities_by_color();
+            assert_eq!(by_color.len(), NUM_COLORS);
+            for &color in COLORS.iter() {
+                assert_eq!(by_color[&color], vec![3]);
+            }
+        }
+
+        let info = compose_hints::<SimpleCardInfo>(&[(Hinted::Color('g'), true), (Hinted::Value(1), false)]);
+        let by_color = info.possibilities_by_color();
+        assert_eq!(by_color.len(), 1);
+        assert_eq!(by_color[&'g'], vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn probabilities_playable_per_slot() {
+        let mut game = deal(&test_opts(2), &["r1 y2 g3 b4 w5", "r2 r3 r4 r5 y1"]);
+        game.process_choice(TurnChoice::Play(0));
+        let mut hand_info = HandInfo::<CardPossibilityTable>::new(4);
+        hand_info.update_for_hint(&Hinted::Value(2), &vec![true, false, false, true]);
+        hand_info.update_for_hint(&Hinted::Color('r'), &vec![true, true, false, false]);
+
+        let probabilities = hand_info.probabilities_playable(&game.board);
+        assert_eq!(probabilities.len(), hand_info.len());
+        for (i, &probability) in probabilities.iter().enumerate() {
+            assert_eq!(probability, hand_info[i].probability_is_playable(&game.board));
+        }
+        // slot 0 is r2, which is playable on top of r1
+        assert_eq!(probabilities[0], 1.0);
+    }
+
+    fn card_set(cards: &str) -> HashSet<Card> {
+        cards.split_whitespace().map(|card| card.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn simple_info_represents_only_rectangles() {
+        assert!(SimpleCardInfo::can_represent_exactly(&card_set("r1 r2 y1 y2")));
+        assert!(SimpleCardInfo::can_represent_exactly(&card_set("g3")));
+        assert!(SimpleCardInfo::can_represent_exactly(&card_set("b1 b2 b3 b4 b5")));
+        assert!(SimpleCardInfo::can_represent_exactly(
+            &SimpleCardInfo::new().get_possibilities().into_iter().collect()));
+
+        assert!(!SimpleCardInfo::can_represent_exactly(&card_set("r1 r2 y1")));
+        assert!(!SimpleCardInfo::can_represent_exactly(&card_set("r1 y2")));
+    }
+
+    #[test]
+    fn color_and_value_probabilities_sum_to_one() {
+        let table = CardPossibilityTable::new();
+        let color_total = COLORS.iter().map(|&color| table.probability_is_color(color)).sum::<f32>();
+        let value_total = VALUES.iter().map(|&value| table.probability_is_value(value)).sum::<f32>();
+        assert!((color_total - 1.0).abs() < 1e-6, "{}", color_total);
+        assert!((value_total - 1.0).abs() < 1e-6, "{}", value_total);
+        assert_eq!(table.probability_is_color('r'), 0.2);
+        assert_eq!(table.probability_is_value(1), 0.3);
+        assert_eq!(table.probability_is_value(5), 0.1);
+    }
+
+    #[test]
+    fn table_with_capacity_has_deck_possibilities() {
+        let table = CardPossibilityTable::with_capacity(&full_deck());
+        assert_eq!(table, CardPossibilityTable::from(&CardCounts::new()));
+        assert_eq!(table, CardPossibilityTable::new());
+        assert_eq!(table.count_possibilities(), NUM_COLORS * NUM_VALUES);
+        assert_eq!(table.total_weight(), full_deck().len() as f32);
+        assert!(table.possible.capacity() >= NUM_COLORS * NUM_VALUES);
+
+        let deck = "r1 r1 y5 b2".split_whitespace().map(|card| card.parse().unwrap()).collect::<Cards>();
+        let table = CardPossibilityTable::with_capacity(&deck);
+        assert_eq!(table.weights_vec(), vec![(Card::new('b', 2), 1), (Card::new('r', 1), 2), (Card::new('y', 5), 1)]);
+    }
+}
@@ -15,6 +15,7 @@ mod strategies {
     pub mod cheating;
     mod hat_helpers;
     pub mod information;
+    pub mod positional;
 }
 
 use getopts::Options;
@@ -63,8 +64,11 @@ fn main() {
                 "Number of players",
                 "NPLAYERS");
     opts.optopt("g", "strategy",
-                "Which strategy to use.  One of 'random', 'cheat', and 'info'",
+                "Which strategy to use.  One of 'random', 'uniform', 'cheat', 'info', and 'positional'",
                 "STRATEGY");
+    opts.optopt("b", "tiebreak",
+                "How the cheating strategy breaks ties between slots.  One of 'lowest' (default), 'highest', and 'random' (seeded by --seed)",
+                "TIEBREAK");
     opts.optflag("h", "help",
                  "Print this help menu");
     opts.optflag("", "results-table",
@@ -115,11 +119,22 @@ fn main() {
     let n_threads = u32::from_str(&matches.opt_str("t").unwrap_or("1".to_string())).unwrap();
     let n_players = u32::from_str(&matches.opt_str("p").unwrap_or("4".to_string())).unwrap();
     let strategy_str : &str = &matches.opt_str("g").unwrap_or("cheat".to_string());
+    let tie_break_str : &str = &matches.opt_str("b").unwrap_or("lowest".to_string());
+    let tie_break = match tie_break_str {
+        "lowest"  => { helpers::TieBreak::LowestSlot }
+        "highest" => { helpers::TieBreak::HighestSlot }
+        "random"  => { helpers::TieBreak::seeded_random(seed.unwrap_or(0)) }
+        _         => {
+            print_usage(&program, opts);
+            panic!("Unexpected tie break argument {}", tie_break_str);
+        }
+    };
 
-    sim_games(n_players, strategy_str, seed, n_trials, n_threads, progress_info).info();
+    sim_games(n_players, strategy_str, tie_break, seed, n_trials, n_threads, progress_info).info();
 }
 
-fn sim_games(n_players: u32, strategy_str: &str, seed: Option<u32>, n_trials: u32, n_threads: u32, progress_info: Option<u32>)
+fn sim_games(n_players: u32, strategy_str: &str, tie_break: helpers::TieBreak, seed: Option<u32>,
+             n_trials: u32, n_threads: u32, progress_info: Option<u32>)
     -> simulator::SimResult {
     let hand_size = match n_players {
         2 => 5,
@@ -136,6 +151,8 @@ fn sim_games(n_players: u32, strategy_str: &str, seed: Option<u32>, n_trials: u3
         num_lives: 3,
         // hanabi rules are a bit ambiguous about whether you can give hints that match 0 cards
         allow_empty_hints: false,
+        refund_hint_on_deck_out: false,
+        zero_score_on_lives_out: false,
     };
 
     let strategy_config : Box<strategy::GameStrategyConfig + Sync> = match strategy_str {
@@ -145,14 +162,23 @@ fn sim_games(n_players: u32, strategy_str: &str, seed: Option<u32>, n_trials: u3
                 play_probability: 0.2,
             }) as Box<strategy::GameStrategyConfig + Sync>
         },
+        "uniform" => {
+            Box::new(strategies::examples::UniformRandomStrategyConfig {
+                seed: seed.unwrap_or(0),
+            }) as Box<strategy::GameStrategyConfig + Sync>
+        },
         "cheat" => {
-            Box::new(strategies::cheating::CheatingStrategyConfig::new())
+            Box::new(strategies::cheating::CheatingStrategyConfig::with_tie_break(tie_break))
                 as Box<strategy::GameStrategyConfig + Sync>
         },
         "info" => {
             Box::new(strategies::information::InformationStrategyConfig::new())
                 as Box<strategy::GameStrategyConfig + Sync>
         },
+        "positional" => {
+            Box::new(strategies::positional::PositionalStrategyConfig::new())
+                as Box<strategy::GameStrategyConfig + Sync>
+        },
         _ => {
             panic!("Unexpected strategy argument {}", strategy_str);
         },
@@ -193,7 +219,7 @@ fn get_results_table() -> String {
                                &|n_players| (format_players(n_players), dashes_long.clone()));
     let mut body = strategies.iter().map(|strategy| {
         make_twolines(&player_nums, (format_name(strategy), space.clone()), &|n_players| {
-            let simresult = sim_games(n_players, strategy, Some(seed), n_trials, n_threads, None);
+            let simresult = sim_games(n_players, strategy, helpers::TieBreak::LowestSlot, Some(seed), n_trials, n_threads, None);
             (
                 format_score(simresult.average_score(), simresult.score_stderr()),
                 format_percent(simresult.percent_perfect(), simresult.percent_perfect_stderr())
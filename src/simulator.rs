@@ -1,23 +1,27 @@
 use rand::{self, Rng, SeedableRng};
 use fnv::FnvHashMap;
+use std::cmp::Ordering;
 use std::fmt;
 use crossbeam;
 
 use game::*;
 use strategy::*;
 
-fn new_deck(seed: u32) -> Cards {
-    let mut deck: Cards = Cards::new();
-
-    for &color in COLORS.iter() {
-        for &value in VALUES.iter() {
-            for _ in 0..get_count_for_value(value) {
-                deck.push(Card::new(color, value));
-            }
-        }
-    };
+// Fisher-Yates shuffle, spelled out so that a seed always gives the same deck
+// regardless of how the rand crate implements shuffling:
+// going from the back, swap each position with a uniformly chosen one at or before it
+pub fn shuffle_deck<R: Rng>(deck: &mut Cards, rng: &mut R) {
+    let mut i = deck.len();
+    while i >= 2 {
+        i -= 1;
+        let j = rng.gen_range(0, i + 1);
+        deck.swap(i, j);
+    }
+}
 
-    rand::ChaChaRng::from_seed(&[seed]).shuffle(&mut deck[..]);
+pub fn new_deck<R: Rng>(rng: &mut R) -> Cards {
+    let mut deck = full_deck();
+    shuffle_deck(&mut deck, rng);
     debug!("Deck: {:?}", deck);
     deck
 }
@@ -27,7 +31,17 @@ pub fn simulate_once(
         game_strategy: Box<GameStrategy>,
         seed: u32,
     ) -> GameState {
-    let deck = new_deck(seed);
+    simulate_once_with_rng(opts, game_strategy, &mut rand::ChaChaRng::from_seed(&[seed]))
+}
+
+// like simulate_once, but the deck is shuffled by the given rng,
+// so that callers can control randomness themselves
+pub fn simulate_once_with_rng<R: Rng>(
+        opts: &GameOptions,
+        game_strategy: Box<GameStrategy>,
+        rng: &mut R,
+    ) -> GameState {
+    let deck = new_deck(rng);
 
     let mut game = GameState::new(opts, deck);
 
@@ -61,10 +75,19 @@ pub fn simulate_once(
     debug!("");
     debug!("=======================================================");
     debug!("Final state:\n{}", game);
-    debug!("SCORE: {:?}", game.score());
+    debug!("SCORE: {:?}", game.final_score());
     game
 }
 
+// tallies the hints given in a finished game, by color or value
+fn count_hints(game: &GameState, hint_counts: &mut FnvHashMap<Hinted, u32>) {
+    for turn in game.board.turn_history.iter() {
+        if let TurnChoice::Hint(ref hint) = turn.choice {
+            *hint_counts.entry(hint.hinted.clone()).or_insert(0) += 1;
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Histogram {
     pub hist: FnvHashMap<Score, u32>,
@@ -153,6 +176,7 @@ pub fn simulate<T: ?Sized>(
 
                 let mut score_histogram = Histogram::new();
                 let mut lives_histogram = Histogram::new();
+                let mut hint_counts = FnvHashMap::default();
 
                 for seed in start..end {
                     if let Some(progress_info_frequency) = progress_info {
@@ -165,40 +189,123 @@ pub fn simulate<T: ?Sized>(
                         }
                     }
                     let game = simulate_once(&opts, strat_config_ref.initialize(&opts), seed);
-                    let score = game.score();
+                    let score = game.final_score();
                     lives_histogram.insert(game.board.lives_remaining);
                     score_histogram.insert(score);
+                    count_hints(&game, &mut hint_counts);
                     if score != PERFECT_SCORE { non_perfect_seeds.push(seed); }
                 }
                 if progress_info.is_some() {
                     info!("Thread {} done", i);
                 }
-                (non_perfect_seeds, score_histogram, lives_histogram)
+                (non_perfect_seeds, score_histogram, lives_histogram, hint_counts)
             }));
         }
 
         let mut non_perfect_seeds : Vec<u32> = Vec::new();
         let mut score_histogram = Histogram::new();
         let mut lives_histogram = Histogram::new();
+        let mut hint_counts = FnvHashMap::default();
         for join_handle in join_handles {
-            let (thread_non_perfect_seeds, thread_score_histogram, thread_lives_histogram) = join_handle.join();
+            let (thread_non_perfect_seeds, thread_score_histogram, thread_lives_histogram, thread_hint_counts) = join_handle.join();
             non_perfect_seeds.extend(thread_non_perfect_seeds.iter());
             score_histogram.merge(thread_score_histogram);
             lives_histogram.merge(thread_lives_histogram);
+            for (hinted, count) in thread_hint_counts.into_iter() {
+                *hint_counts.entry(hinted).or_insert(0) += count;
+            }
         }
 
         non_perfect_seeds.sort();
         SimResult {
             scores: score_histogram,
             lives: lives_histogram,
+            hint_counts: hint_counts,
             non_perfect_seed: non_perfect_seeds.get(0).cloned(),
         }
     })
 }
 
+// fewest games simulate_until plays before trusting the standard error
+const MIN_GAMES_FOR_CONFIDENCE: u32 = 30;
+
+// simulates games one seed at a time, until the 95% confidence interval of the
+// average score is narrower than target_ci_width, or max_games have been played
+pub fn simulate_until<T: ?Sized>(
+        opts: &GameOptions,
+        strat_config: Box<T>,
+        first_seed_opt: Option<u32>,
+        target_ci_width: f32,
+        max_games: u32,
+    ) -> SimResult
+    where T: GameStrategyConfig {
+
+    let first_seed = first_seed_opt.unwrap_or_else(|| rand::thread_rng().next_u32());
+
+    let mut non_perfect_seeds = Vec::new();
+    let mut score_histogram = Histogram::new();
+    let mut lives_histogram = Histogram::new();
+    let mut hint_counts = FnvHashMap::default();
+    for seed in first_seed..(first_seed + max_games) {
+        let game = simulate_once(&opts, strat_config.initialize(&opts), seed);
+        let score = game.final_score();
+        lives_histogram.insert(game.board.lives_remaining);
+        score_histogram.insert(score);
+        count_hints(&game, &mut hint_counts);
+        if score != PERFECT_SCORE { non_perfect_seeds.push(seed); }
+
+        if score_histogram.total_count >= MIN_GAMES_FOR_CONFIDENCE {
+            let ci_width = 2.0 * 1.96 * score_histogram.stdev_of_average();
+            if ci_width < target_ci_width {
+                info!("Stopping after {} games, with confidence interval width {}",
+                      score_histogram.total_count, ci_width);
+                break;
+            }
+        }
+    }
+
+    SimResult {
+        scores: score_histogram,
+        lives: lives_histogram,
+        hint_counts: hint_counts,
+        non_perfect_seed: non_perfect_seeds.get(0).cloned(),
+    }
+}
+
+// simulates each parameter setting on the same seeds, and returns the results
+// ordered from best to worst average score
+pub fn sweep<P, F>(
+        opts: &GameOptions,
+        params: Vec<P>,
+        make_config: F,
+        first_seed: u32,
+        n_trials: u32,
+        n_threads: u32,
+    ) -> Vec<(P, SimResult)>
+    where P: fmt::Debug, F: Fn(&P) -> Box<GameStrategyConfig + Sync> {
+
+    let mut results = params.into_iter().map(|param| {
+        let result = simulate(opts, make_config(&param), Some(first_seed), n_trials, n_threads, None);
+        (param, result)
+    }).collect::<Vec<_>>();
+    results.sort_by(|a, b| {
+        b.1.average_score().partial_cmp(&a.1.average_score()).unwrap_or(Ordering::Equal)
+    });
+
+    for (rank, &(ref param, ref result)) in results.iter().enumerate() {
+        info!(
+            "{}. {:?}: {} ± {} score, {}% win",
+            rank + 1, param, result.average_score(), result.score_stderr(), result.percent_perfect()
+        );
+    }
+    results
+}
+
 pub struct SimResult {
     pub scores: Histogram,
     pub lives: Histogram,
+    // how often each color and value was hinted, over all games
+    pub hint_counts: FnvHashMap<Hinted, u32>,
     pub non_perfect_seed: Option<u32>,
 }
 
@@ -236,5 +343,131 @@ impl SimResult {
         info!("Percentage perfect: {:?}%", self.percent_perfect());
         info!("Average score: {:?}", self.average_score());
         info!("Average lives: {:?}", self.average_lives());
+
+        let hinteds = COLORS.iter().map(|&color| Hinted::Color(color))
+            .chain(VALUES.iter().map(|&value| Hinted::Value(value)));
+        let hint_counts = hinteds.map(|hinted| {
+            format!("{}: {}", hinted, self.hint_counts.get(&hinted).unwrap_or(&0))
+        }).collect::<Vec<_>>();
+        info!("Hints given: {}", hint_counts.join(", "));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{ChaChaRng, SeedableRng};
+
+    use super::*;
+    use game::tests::{deal, test_opts};
+    use strategies::cheating::CheatingStrategyConfig;
+    use strategies::examples::{RandomStrategyConfig, UniformRandomStrategyConfig};
+
+    // only ever discards its first card, so every game scores 0
+    fn discard_only() -> RandomStrategyConfig {
+        RandomStrategyConfig {
+            hint_probability: 0.0,
+            play_probability: 0.0,
+        }
+    }
+
+    fn deck_string(seed: u32) -> String {
+        let deck = new_deck(&mut ChaChaRng::from_seed(&[seed]));
+        deck.iter().map(|card| format!("{}", card)).collect::<Vec<_>>().join(" ")
+    }
+
+    // the published results depend on these exact decks, so they must not change
+    // when the shuffle or the rand crate is touched
+    #[test]
+    fn seeded_decks_are_pinned() {
+        assert_eq!(deck_string(0),
+                   "b1 r3 y4 r4 b3 b5 y5 y3 y1 b3 g1 g5 g1 w3 r1 y1 r5 g2 r2 g3 b4 g4 r4 w1 y3 \
+                    b1 r2 b2 w5 w4 g1 g4 y1 y2 w3 b2 r1 w2 r3 g2 y2 w2 w1 w4 g3 y4 r1 b1 b4 w1");
+        assert_eq!(deck_string(1),
+                   "r3 w5 b2 b4 b3 w1 y4 w4 b2 y1 w2 y3 g1 r2 r1 b4 b3 g2 r3 b1 y4 g1 w3 g3 b1 \
+                    w3 y1 w2 w1 w1 r1 g4 w4 y2 b1 g2 y3 r1 y2 g5 r5 y1 b5 g3 r4 r4 g1 r2 y5 g4");
+    }
+
+    #[test]
+    fn sweep_ranks_best_setting_first() {
+        let opts = test_opts(3);
+        let results = sweep(&opts, vec!["uniform", "discard", "cheat"], |&name| {
+            match name {
+                "uniform" => Box::new(UniformRandomStrategyConfig { seed: 0 }) as Box<GameStrategyConfig + Sync>,
+                "discard" => Box::new(discard_only()) as Box<GameStrategyConfig + Sync>,
+                _ => Box::new(CheatingStrategyConfig::new()) as Box<GameStrategyConfig + Sync>,
+            }
+        }, 0, 10, 2);
+        let names = results.iter().map(|&(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["cheat", "uniform", "discard"]);
+        for &(_, ref result) in &results {
+            assert_eq!(result.scores.total_count, 10);
+        }
+        assert_eq!(results[2].1.average_score(), 0.0);
+    }
+
+    #[test]
+    fn simulate_until_stops_early_without_variance() {
+        let opts = test_opts(3);
+        let result = simulate_until(&opts, Box::new(discard_only()), Some(0), 0.5, 1000);
+        assert_eq!(result.scores.total_count, MIN_GAMES_FOR_CONFIDENCE);
+        assert_eq!(result.average_score(), 0.0);
+    }
+
+    #[test]
+    fn simulate_until_stops_at_max_games_with_variance() {
+        let opts = test_opts(3);
+        let strategy = Box::new(UniformRandomStrategyConfig { seed: 0 });
+        let result = simulate_until(&opts, strategy, Some(0), 0.01, 2 * MIN_GAMES_FOR_CONFIDENCE);
+        assert_eq!(result.scores.total_count, 2 * MIN_GAMES_FOR_CONFIDENCE);
+        assert!(result.score_stderr() > 0.0);
+    }
+
+    #[test]
+    fn count_hints_tallies_each_color_and_value() {
+        let opts = test_opts(2);
+        let mut game = deal(&opts, &["r1 y2 g3 b4 w5", "r1 r2 r3 r4 r5"]);
+        let hint = |player, hinted| TurnChoice::Hint(Hint { player: player, hinted: hinted });
+        game.process_choices(vec![
+            hint(1, Hinted::Color('r')),
+            hint(0, Hinted::Color('r')),
+            hint(1, Hinted::Value(1)),
+            hint(0, Hinted::Value(1)),
+            hint(1, Hinted::Color('r')),
+        ]).unwrap();
+        let mut hint_counts = FnvHashMap::default();
+        count_hints(&game, &mut hint_counts);
+        assert_eq!(hint_counts[&Hinted::Color('r')], 3);
+        assert_eq!(hint_counts[&Hinted::Value(1)], 2);
+        assert_eq!(hint_counts.len(), 2);
+    }
+
+    #[test]
+    fn simulate_merges_hint_counts_across_threads() {
+        let opts = test_opts(3);
+        let mut expected = FnvHashMap::default();
+        for seed in 0..12 {
+            count_hints(&simulate_once(&opts, CheatingStrategyConfig::new().initialize(&opts), seed), &mut expected);
+        }
+        assert!(expected.len() > 1);
+        for n_threads in vec![1, 3, 4] {
+            let result = simulate(&opts, Box::new(CheatingStrategyConfig::new()), Some(0), 12, n_threads, None);
+            assert_eq!(result.hint_counts, expected, "{} threads", n_threads);
+        }
+    }
+
+    #[test]
+    fn injected_rng_reproduces_seeded_game() {
+        let opts = test_opts(4);
+        for seed in 0..5 {
+            let seeded = simulate_once(&opts, CheatingStrategyConfig::new().initialize(&opts), seed);
+            let mut rng = ChaChaRng::from_seed(&[seed]);
+            let injected = simulate_once_with_rng(&opts, CheatingStrategyConfig::new().initialize(&opts), &mut rng);
+            assert_eq!(injected.dump(), seeded.dump());
+            assert_eq!(injected.board.turn_history, seeded.board.turn_history);
+        }
+        // the known game for the pinned seed 0 deck
+        let game = simulate_once_with_rng(&opts, CheatingStrategyConfig::new().initialize(&opts), &mut ChaChaRng::from_seed(&[0]));
+        assert_eq!(game.final_score(), 25);
+        assert_eq!(game.board.turn, 40);
     }
 }
@@ -4,6 +4,7 @@ use fnv::{FnvHashMap, FnvHashSet};
 
 use strategy::*;
 use game::*;
+use helpers::TieBreak;
 
 // strategy that explicitly cheats by using Rc/RefCell
 // serves as a reference point for other strategies
@@ -16,28 +17,39 @@ use game::*;
 //  - if a card is discardable, discard it
 //  - if a hint exists, hint
 //  - discard the first card
+// Ties between equally good cards are broken according to the TieBreak.
 
-pub struct CheatingStrategyConfig;
+pub struct CheatingStrategyConfig {
+    tie_break: TieBreak,
+}
 
 impl CheatingStrategyConfig {
     pub fn new() -> CheatingStrategyConfig {
-        CheatingStrategyConfig
+        CheatingStrategyConfig::with_tie_break(TieBreak::LowestSlot)
+    }
+
+    pub fn with_tie_break(tie_break: TieBreak) -> CheatingStrategyConfig {
+        CheatingStrategyConfig {
+            tie_break: tie_break,
+        }
     }
 }
 impl GameStrategyConfig for CheatingStrategyConfig {
     fn initialize(&self, _: &GameOptions) -> Box<GameStrategy> {
-        Box::new(CheatingStrategy::new())
+        Box::new(CheatingStrategy::new(self.tie_break.clone()))
     }
 }
 
 pub struct CheatingStrategy {
     player_hands_cheat: Rc<RefCell<FnvHashMap<Player, Cards>>>,
+    tie_break: TieBreak,
 }
 
 impl CheatingStrategy {
-    pub fn new() -> CheatingStrategy {
+    pub fn new(tie_break: TieBreak) -> CheatingStrategy {
         CheatingStrategy {
             player_hands_cheat: Rc::new(RefCell::new(FnvHashMap::default())),
+            tie_break: tie_break,
         }
     }
 }
@@ -50,6 +62,7 @@ impl GameStrategy for CheatingStrategy {
         }
         Box::new(CheatingPlayerStrategy {
             player_hands_cheat: self.player_hands_cheat.clone(),
+            tie_break: self.tie_break.clone(),
             me: player,
         })
     }
@@ -57,6 +70,7 @@ impl GameStrategy for CheatingStrategy {
 
 pub struct CheatingPlayerStrategy {
     player_hands_cheat: Rc<RefCell<FnvHashMap<Player, Cards>>>,
+    tie_break: TieBreak,
     me: Player,
 }
 impl CheatingPlayerStrategy {
@@ -148,17 +162,13 @@ impl PlayerStrategy for CheatingPlayerStrategy {
         if playable_cards.len() > 0 {
             // play the best playable card
             // the higher the play_score, the better to play
-            let mut index = 0;
-            let mut play_score = -1;
-
-            for &(i, card) in playable_cards.iter() {
-                let score = self.get_play_score(view, card);
-                if score > play_score {
-                    index = i;
-                    play_score = score;
-                }
-            }
-            return TurnChoice::Play(index)
+            let play_scores = playable_cards.iter().map(|&(i, card)| {
+                (i, self.get_play_score(view, card))
+            }).collect::<Vec<_>>();
+            let best_score = play_scores.iter().map(|&(_, score)| score).max().unwrap();
+            let best_indices = play_scores.iter().filter(|&&(_, score)| score == best_score)
+                .map(|&(i, _)| i).collect::<Vec<_>>();
+            return TurnChoice::Play(self.tie_break.choose(&best_indices).unwrap())
         }
 
         // discard threshold is how many cards we're willing to discard
@@ -193,20 +203,17 @@ impl PlayerStrategy for CheatingPlayerStrategy {
         // Play the best discardable card, according to the ordering induced by comparing
         //   (is in another hand, is dispensable, value)
         // The higher, the better to discard
-        let mut index = 0;
-        let mut compval = (false, false, 0);
-        for (i, card) in my_hand.iter().enumerate() {
-            let my_compval = (
+        let compvals = my_hand.iter().map(|card| {
+            (
                 view.can_see(card),
                 view.board.is_dispensable(card),
                 card.value,
-            );
-            if my_compval > compval {
-                index = i;
-                compval = my_compval;
-            }
-        }
-        TurnChoice::Discard(index)
+            )
+        }).collect::<Vec<_>>();
+        let best_compval = compvals.iter().max().unwrap();
+        let best_indices = (0..compvals.len()).filter(|&i| compvals[i] == *best_compval)
+            .collect::<Vec<_>>();
+        TurnChoice::Discard(self.tie_break.choose(&best_indices).unwrap())
     }
     fn update(&mut self, _: &TurnRecord, _: &BorrowedGameView) {
     }
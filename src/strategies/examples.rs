@@ -1,6 +1,6 @@
 use strategy::*;
 use game::*;
-use rand::{self, Rng};
+use rand::{self, ChaChaRng, Rng, SeedableRng};
 
 // dummy, terrible strategy, as an example
 #[derive(Clone)]
@@ -69,3 +69,91 @@ impl PlayerStrategy for RandomStrategyPlayer {
     fn update(&mut self, _: &TurnRecord, _: &BorrowedGameView) {
     }
 }
+
+// picks uniformly among all legal choices
+// a baseline to compare against, and a quick way to exercise the game engine.
+// each player draws from its own rng, seeded from the seed and the player,
+// so a game can be replayed given this seed and the deck
+#[derive(Clone)]
+pub struct UniformRandomStrategyConfig {
+    pub seed: u32,
+}
+
+impl GameStrategyConfig for UniformRandomStrategyConfig {
+    fn initialize(&self, _: &GameOptions) -> Box<GameStrategy> {
+        Box::new(UniformRandomStrategy {
+            seed: self.seed,
+        })
+    }
+}
+
+pub struct UniformRandomStrategy {
+    seed: u32,
+}
+impl GameStrategy for UniformRandomStrategy {
+    fn initialize(&self, player: Player, _: &BorrowedGameView) -> Box<PlayerStrategy> {
+        Box::new(UniformRandomStrategyPlayer {
+            rng: ChaChaRng::from_seed(&[self.seed, player]),
+        })
+    }
+}
+
+pub struct UniformRandomStrategyPlayer {
+    rng: ChaChaRng,
+}
+
+impl PlayerStrategy for UniformRandomStrategyPlayer {
+    fn decide(&mut self, view: &BorrowedGameView) -> TurnChoice {
+        let choices = view.legal_choices();
+        self.rng.choose(&choices).unwrap().clone()
+    }
+    fn update(&mut self, _: &TurnRecord, _: &BorrowedGameView) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::tests::test_opts;
+    use simulator::new_deck;
+
+    // plays a seeded game with the uniform random strategy, validating the state after every turn
+    fn play_validated(num_players: u32, seed: u32) -> GameState {
+        let opts = test_opts(num_players);
+        let strategy = UniformRandomStrategyConfig { seed: seed }.initialize(&opts);
+        let mut game = GameState::new(&opts, new_deck(&mut ChaChaRng::from_seed(&[seed])));
+        game.validate().unwrap();
+        let mut players = game.get_players().map(|player| {
+            strategy.initialize(player, &game.get_view(player))
+        }).collect::<Vec<_>>();
+        while !game.is_over() {
+            let player = game.board.player;
+            let choice = players[player as usize].decide(&game.get_view(player));
+            let turn_record = game.process_choice(choice);
+            for player in game.get_players() {
+                players[player as usize].update(&turn_record, &game.get_view(player));
+            }
+            game.validate().unwrap_or_else(|err| panic!("{}\n{}", err, game.dump()));
+        }
+        game
+    }
+
+    #[test]
+    fn uniform_random_games_stay_valid() {
+        for num_players in 2..6 {
+            for seed in 0..50 {
+                play_validated(num_players, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn uniform_random_games_are_reproducible() {
+        for seed in 0..5 {
+            let game = play_validated(3, seed);
+            let again = play_validated(3, seed);
+            assert_eq!(game.board.turn_history, again.board.turn_history);
+            assert_eq!(game.dump(), again.dump());
+        }
+    }
+}
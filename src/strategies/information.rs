@@ -799,14 +799,9 @@ impl InformationPlayerStrategy {
         if view.board.lives_remaining > 1 &&
            view.board.discard_size() <= discard_threshold
         {
-            let mut risky_playable_cards = private_info.iter().enumerate().filter(|&(_, card_table)| {
+            let mut risky_playable_cards = private_info.iter().enumerate().filter_map(|(i, card_table)| {
                 // card is either playable or dead
-                card_table.probability_of_predicate(&|card| {
-                    view.board.is_playable(card) || view.board.is_dead(card)
-                }) == 1.0
-            }).map(|(i, card_table)| {
-                let p = card_table.probability_is_playable(&view.board);
-                (i, card_table, p)
+                card_table.playable_or_dead_probability(&view.board).map(|p| (i, card_table, p))
             }).collect::<Vec<_>>();
 
             if risky_playable_cards.len() > 0 {
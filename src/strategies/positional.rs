@@ -0,0 +1,202 @@
+use fnv::FnvHashMap;
+
+use strategy::*;
+use game::*;
+
+// strategy where hints encode a position rather than an identity
+//
+// Every hint is a play hint: the first (oldest) card it touches should be played.
+// All players track which slots have been marked this way, which is public knowledge.
+//
+// Plays according to the following rules:
+//  - if any of my slots is marked, play the first one
+//  - if a hint is available, and some other player has an unmarked playable card that
+//      can be made the first touched card of a hint, give that hint
+//  - discard the first unmarked card
+
+pub struct PositionalStrategyConfig;
+
+impl PositionalStrategyConfig {
+    pub fn new() -> PositionalStrategyConfig {
+        PositionalStrategyConfig
+    }
+}
+impl GameStrategyConfig for PositionalStrategyConfig {
+    fn initialize(&self, _: &GameOptions) -> Box<GameStrategy> {
+        Box::new(PositionalStrategy::new())
+    }
+}
+
+pub struct PositionalStrategy;
+
+impl PositionalStrategy {
+    pub fn new() -> PositionalStrategy {
+        PositionalStrategy
+    }
+}
+impl GameStrategy for PositionalStrategy {
+    fn initialize(&self, player: Player, view: &BorrowedGameView) -> Box<PlayerStrategy> {
+        Box::new(PositionalPlayerStrategy::new(player, view))
+    }
+}
+
+// the slots of a hand that a hint would touch
+pub fn touched_slots(hand: &Cards, hinted: &Hinted) -> Vec<usize> {
+    hand.iter().enumerate().filter(|&(_, card)| {
+        match hinted {
+            &Hinted::Color(color) => card.color == color,
+            &Hinted::Value(value) => card.value == value,
+        }
+    }).map(|(i, _)| i).collect()
+}
+
+// the slot a hint asks to play, given which cards it touched
+pub fn clue_focus(matches: &Vec<bool>) -> Option<usize> {
+    matches.iter().position(|&matched| matched)
+}
+
+pub struct PositionalPlayerStrategy {
+    // for each player, which slots have been marked as playable
+    marked: FnvHashMap<Player, Vec<bool>>,
+    me: Player,
+}
+impl PositionalPlayerStrategy {
+    fn new(player: Player, view: &BorrowedGameView) -> PositionalPlayerStrategy {
+        let marked = view.board.get_players().map(|player| {
+            (player, vec![false; view.hand_size(&player)])
+        }).collect::<FnvHashMap<_, _>>();
+        PositionalPlayerStrategy {
+            marked: marked,
+            me: player,
+        }
+    }
+
+    fn is_marked_elsewhere(&self, view: &BorrowedGameView, card: &Card) -> bool {
+        view.get_other_players().iter().any(|player| {
+            let marked = self.marked.get(player).unwrap();
+            view.get_hand(player).iter().zip(marked.iter()).any(|(other_card, &is_marked)| {
+                is_marked && other_card == card
+            })
+        })
+    }
+
+    // find a hint whose focus is a playable card, that nobody is already going to play
+    fn find_play_hint(&self, view: &BorrowedGameView) -> Option<Hint> {
+        let mut player = view.board.player_to_left(&self.me);
+        while player != self.me {
+            let hand = view.get_hand(&player);
+            let marked = self.marked.get(&player).unwrap();
+            for (i, card) in hand.iter().enumerate() {
+                if marked[i] || !view.board.is_playable(card) || self.is_marked_elsewhere(view, card) {
+                    continue;
+                }
+                for hinted in vec![Hinted::Color(card.color), Hinted::Value(card.value)] {
+                    if touched_slots(hand, &hinted).first() == Some(&i) {
+                        return Some(Hint {
+                            player: player,
+                            hinted: hinted,
+                        });
+                    }
+                }
+            }
+            player = view.board.player_to_left(&player);
+        }
+        None
+    }
+}
+impl PlayerStrategy for PositionalPlayerStrategy {
+    fn decide(&mut self, view: &BorrowedGameView) -> TurnChoice {
+        let my_marked = self.marked.get(&self.me).unwrap();
+        if let Some(index) = my_marked.iter().position(|&is_marked| is_marked) {
+            return TurnChoice::Play(index);
+        }
+
+        if view.board.hints_remaining > 0 {
+            if let Some(hint) = self.find_play_hint(view) {
+                return TurnChoice::Hint(hint);
+            }
+        }
+
+        // nothing is marked, so the first card is unmarked
+        TurnChoice::Discard(0)
+    }
+
+    fn update(&mut self, turn_record: &TurnRecord, view: &BorrowedGameView) {
+        match turn_record.choice {
+            TurnChoice::Hint(ref hint) => {
+                if let TurnResult::Hint(ref matches) = turn_record.result {
+                    if let Some(index) = clue_focus(matches) {
+                        self.marked.get_mut(&hint.player).unwrap()[index] = true;
+                    }
+                }
+            }
+            TurnChoice::Discard(index) | TurnChoice::Play(index) => {
+                let hand_size = view.hand_size(&turn_record.player);
+                let marked = self.marked.get_mut(&turn_record.player).unwrap();
+                marked.remove(index);
+                if marked.len() < hand_size {
+                    marked.push(false);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::tests::{deal, test_opts};
+
+    #[test]
+    fn touched_slots_matches_color_or_value() {
+        let hand = "r1 y2 r3 g2 w5".split_whitespace().map(|card| card.parse().unwrap()).collect::<Cards>();
+        assert_eq!(touched_slots(&hand, &Hinted::Color('r')), vec![0, 2]);
+        assert_eq!(touched_slots(&hand, &Hinted::Value(2)), vec![1, 3]);
+        assert_eq!(touched_slots(&hand, &Hinted::Value(5)), vec![4]);
+        assert_eq!(touched_slots(&hand, &Hinted::Color('b')), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn clue_focus_is_first_touched_slot() {
+        assert_eq!(clue_focus(&vec![false, true, false, true]), Some(1));
+        assert_eq!(clue_focus(&vec![true, true]), Some(0));
+        assert_eq!(clue_focus(&vec![false, false, false]), None);
+        assert_eq!(clue_focus(&vec![]), None);
+    }
+
+    // processes the choice, and updates every player's strategy with it
+    fn process(game: &mut GameState, players: &mut [PositionalPlayerStrategy], choice: TurnChoice) {
+        let turn_record = game.process_choice(choice);
+        for player in game.get_players() {
+            players[player as usize].update(&turn_record, &game.get_view(player));
+        }
+    }
+
+    fn marked(players: &[PositionalPlayerStrategy], player: Player) -> Vec<Vec<bool>> {
+        players.iter().map(|strategy| strategy.marked.get(&player).unwrap().clone()).collect()
+    }
+
+    #[test]
+    fn update_marks_focus_and_shifts_it() {
+        let opts = test_opts(2);
+        let mut game = deal(&opts, &["r1 r2 r3 r4 r5", "y3 g1 y4 b2 w2"]);
+        let mut players = game.get_players().map(|player| {
+            PositionalPlayerStrategy::new(player, &game.get_view(player))
+        }).collect::<Vec<_>>();
+
+        // the hint touches slots 3 and 4, and marks only slot 3
+        process(&mut game, &mut players, TurnChoice::Hint(Hint { player: 1, hinted: Hinted::Value(2) }));
+        assert_eq!(marked(&players, 1), vec![vec![false, false, false, true, false]; 2]);
+        assert_eq!(players[1].decide(&game.get_view(1)), TurnChoice::Play(3));
+
+        // discarding an earlier slot shifts the mark down, and the drawn card is unmarked
+        process(&mut game, &mut players, TurnChoice::Discard(0));
+        assert_eq!(marked(&players, 1), vec![vec![false, false, true, false, false]; 2]);
+
+        // playing the marked slot clears it
+        process(&mut game, &mut players, TurnChoice::Discard(0));
+        process(&mut game, &mut players, TurnChoice::Play(2));
+        assert_eq!(marked(&players, 1), vec![vec![false; 5]; 2]);
+        assert_eq!(marked(&players, 0), vec![vec![false; 5]; 2]);
+    }
+}